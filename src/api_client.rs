@@ -1,330 +1,472 @@
-use async_recursion::async_recursion;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use serde_json::json;
-use std::io::{Error, ErrorKind};
-
-use crate::consts::SHC_BACKEND_API_BASE_URL;
-use crate::models::{AddFileResponse, RefreshTokenResponse, ShcFile, ShcFileResponse};
+use std::io::IsTerminal;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_stream::Stream;
+
+use crate::command::auth;
+use crate::consts::TOKEN_REFRESH_MARGIN_SECS;
+use crate::error::ShcError;
+use crate::http::{self, HttpSettings};
+use crate::models::{
+    AddFileResponse, CompletedPart, MultipartUploadResponse, RefreshTokenResponse, ShareLimits,
+    ShcFile, ShcFileResponse, UploadPartResponse,
+};
+use crate::tui;
 use crate::user_config::UserConfig;
 
+// tokens of the logged in user, shared by all clones so a refresh done by one of them
+// is used by the others instead of refreshing again with an already rotated refresh token
+#[derive(Default)]
+struct Session {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+}
+
+// whether a request does the same thing when sent twice, only those go through http::send
+#[derive(Clone, Copy)]
+enum Repeat {
+    Safe,
+    Once,
+}
+
+// clones share the connection pool, e.g. one per concurrent upload
+#[derive(Clone)]
 pub struct ApiClient {
     api_base_url: String,
-
-    // why we tried_refreshing_token?
-    tried_refreshing_token: bool,
     user_config: UserConfig,
+    session: Arc<Mutex<Session>>,
+    // ask for a new login when the session expired instead of failing, only with a terminal
+    login_again: bool,
 
     // what is meaning of reqwest::Client?
     client: reqwest::Client,
+    http: HttpSettings,
 }
 
 impl ApiClient {
-    pub fn new(user_config: UserConfig) -> ApiClient {
+    pub fn new(user_config: UserConfig, http: HttpSettings) -> ApiClient {
+        let session = Session {
+            access_token: user_config.user.access_token.clone(),
+            refresh_token: user_config.user.refresh_token.clone(),
+        };
         ApiClient {
-            api_base_url: SHC_BACKEND_API_BASE_URL.to_string(),
-            tried_refreshing_token: false,
+            api_base_url: user_config.api_base_url().to_string(),
             // if we have not assigned anything to user_config then what will be the value of user_config?
             user_config,
-            client: reqwest::Client::new(),
+            session: Arc::new(Mutex::new(session)),
+            login_again: std::io::stdin().is_terminal(),
+            client: http.api_client(),
+            http,
         }
     }
 
-    // what does this function do? do we have to explicitly pass  &mut self?
-    pub fn login_again(&mut self) {
-        self.tried_refreshing_token = true;
-        println!("Logged out, please login again");
-        // TODO: logout - clear config
-        // TODO: run login command (can we continue after login command?)
+    // for the uploads and downloads that go to storage instead of the API
+    pub fn http(&self) -> HttpSettings {
+        self.http
+    }
+
+    pub fn share_link(&self, file_id: &str) -> String {
+        self.user_config.share_link(file_id)
+    }
+
+    pub fn profile(&self) -> &str {
+        &self.user_config.profile
+    }
+
+    // e.g. off while a full screen UI owns the terminal
+    pub fn set_login_again(&mut self, login_again: bool) {
+        self.login_again = login_again;
+    }
+
+    // sends the request built by `request` with the access token of the user
+    // a token about to expire is refreshed first, and after a 401 the token is refreshed
+    // and the request sent once more, a second 401 means the login is not accepted any more
+    async fn send_authed(
+        &mut self,
+        repeat: Repeat,
+        request: impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> Result<Response, ShcError> {
+        let mut access_token = self
+            .session
+            .lock()
+            .await
+            .access_token
+            .clone()
+            .ok_or(ShcError::AuthExpired)?;
+        if expires_soon(&access_token) {
+            access_token = self.refresh_token(&access_token).await?;
+        }
+
+        let res = self.send_with(repeat, &access_token, &request).await?;
+        if res.status() != StatusCode::UNAUTHORIZED {
+            return Ok(res);
+        }
 
-        // what is std and what is process?
-        std::process::exit(1);
+        access_token = self.refresh_token(&access_token).await?;
+        let res = self.send_with(repeat, &access_token, &request).await?;
+        match res.status() {
+            StatusCode::UNAUTHORIZED => Err(ShcError::AuthExpired),
+            _ => Ok(res),
+        }
     }
 
-    async fn refresh_token(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // what is the use of self here?
-        if self.tried_refreshing_token {
-            // what is the use of clear here?
-            self.user_config.clear();
-            self.login_again();
+    async fn send_with(
+        &self,
+        repeat: Repeat,
+        access_token: &str,
+        request: &impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> Result<Response, ShcError> {
+        let request = || request(&self.client).header("Authorization", access_token);
+        match repeat {
+            Repeat::Safe => http::send(&self.http, request).await,
+            Repeat::Once => Ok(request().send().await?),
         }
+    }
 
+    // returns the new access token, `rejected` is the one that did not work
+    async fn refresh_token(&mut self, rejected: &str) -> Result<String, ShcError> {
+        // held until the new tokens are stored, so only one clone refreshes at a time
+        let mut session = self.session.lock().await;
+        if let Some(access_token) = session.access_token.as_ref() {
+            // another clone refreshed while this one was waiting
+            if access_token != rejected {
+                return Ok(access_token.clone());
+            }
+        }
+        let refresh_token = session.refresh_token.clone().ok_or(ShcError::AuthExpired)?;
+
+        // not retried, a refresh that went through but lost its answer already rotated the token
         let res = self
             .client
             .get(format!("{}/auth/refresh-token", self.api_base_url))
-            .header(
-                "Authorization",
-                // what is meaning of as_ref and unwrap?
-                self.user_config.user.refresh_token.as_ref().unwrap(),
-            )
+            .header("Authorization", refresh_token)
             .send()
             // what is meaning of await? ?
             .await?;
 
         match res.status() {
-            reqwest::StatusCode::OK => match res.json::<RefreshTokenResponse>().await {
-                Ok(res) => {
-                    self.user_config.user.email = Some(res.user.email);
-                    self.user_config.user.name = Some(res.user.name);
-                    self.user_config.user.user_id = Some(res.user.id);
-                    self.user_config.user.access_token = Some(res.access_token);
-                    self.user_config.user.refresh_token = Some(res.refresh_token);
-                    self.user_config.save();
+            StatusCode::OK => {
+                let res = res.json::<RefreshTokenResponse>().await?;
+                self.user_config.user.email = Some(res.user.email);
+                self.user_config.user.name = Some(res.user.name);
+                self.user_config.user.user_id = Some(res.user.id);
+                self.user_config.user.access_token = Some(res.access_token.clone());
+                self.user_config.user.refresh_token = Some(res.refresh_token.clone());
+                self.user_config.save();
+
+                session.access_token = Some(res.access_token.clone());
+                session.refresh_token = Some(res.refresh_token);
+                Ok(res.access_token)
+            }
+            // the refresh token expired or was revoked, only a new login helps
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                *session = Session::default();
+                if !self.login_again {
+                    self.user_config.clear();
+                    return Err(ShcError::AuthExpired);
                 }
-                Err(e) => {
-                    return Err(e.into());
+
+                // the other clones wait for the lock meanwhile and then use the new token
+                let _hidden = tui::hide_progress();
+                eprintln!("Your session expired, please login again");
+                if let Err(e) = auth::login(&mut self.user_config, self.http).await {
+                    self.user_config.clear();
+                    return Err(e);
                 }
-            },
-            _ => {
-                self.login_again();
+                session.access_token = self.user_config.user.access_token.clone();
+                session.refresh_token = self.user_config.user.refresh_token.clone();
+                session.access_token.clone().ok_or(ShcError::AuthExpired)
             }
+            _ => Err(ShcError::from_response(res).await),
         }
-        Ok(())
     }
 
-    #[async_recursion]
     pub async fn list_files(
         &mut self,
         search: &str,
-    ) -> Result<ShcFileResponse, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
-
+        page: u64,
+        limit: u64,
+    ) -> Result<ShcFileResponse, ShcError> {
+        let url = format!("{}/api/files", self.api_base_url);
         let res = self
-            .client
-            .get(format!(
-                "{}/api/files?search={}&page=1&limit=100",
-                self.api_base_url, search
-            ))
-            .header("Authorization", access_token)
-            .send()
+            .send_authed(Repeat::Safe, |client| {
+                client.get(&url).query(&[
+                    ("search", search.to_string()),
+                    ("page", page.to_string()),
+                    ("limit", limit.to_string()),
+                ])
+            })
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => {
-                let res = res.json::<ShcFileResponse>().await?;
-                Ok(res)
-            }
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.list_files(search).await;
-            }
-            _ => {
-                // TODO: use server error message
-                Err(Error::new(ErrorKind::Other, "Something went wrong").into())
+        parse_json(res).await
+    }
+
+    // walks every page of the files matching `search`, a page is only fetched once
+    // the files of the previous one have been consumed
+    pub fn list_all_files<'a>(
+        &'a mut self,
+        search: &'a str,
+        limit: u64,
+    ) -> impl Stream<Item = Result<ShcFile, ShcError>> + 'a {
+        async_stream::try_stream! {
+            let mut page = 1;
+            loop {
+                let res = self.list_files(search, page, limit).await?;
+                for file in res.results {
+                    yield file;
+                }
+                match res.next_page {
+                    Some(next_page) if next_page > page => page = next_page,
+                    _ => break,
+                }
             }
         }
     }
 
-    #[async_recursion]
-    pub async fn remove_file(&mut self, file_id: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
-
+    pub async fn remove_file(&mut self, file_id: &str) -> Result<(), ShcError> {
+        let url = format!("{}/api/files/remove/{}", self.api_base_url, file_id);
         let res = self
-            .client
-            .delete(format!(
-                "{}/api/files/remove/{}",
-                self.api_base_url, file_id
-            ))
-            .header("Authorization", access_token)
-            .send()
+            .send_authed(Repeat::Once, |client| client.delete(&url))
             .await?;
 
-        // explain below match block in detail
-        match res.status() {
-            reqwest::StatusCode::OK => Ok(()),
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.remove_file(file_id).await;
-            }
-            // what is meaning of below code in detail ?
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        expect_ok(res).await
     }
 
-    // what is #[async_recursion]?
-    #[async_recursion]
-    pub async fn toggle_file_visibility(
-        &mut self,
-        file_id: &str,
-    ) -> Result<ShcFile, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
-
+    pub async fn toggle_file_visibility(&mut self, file_id: &str) -> Result<ShcFile, ShcError> {
+        let url = format!(
+            "{}/api/files/toggle-visibility/{}",
+            self.api_base_url, file_id
+        );
         let res = self
-            .client
-            .patch(format!(
-                "{}/api/files/toggle-visibility/{}",
-                self.api_base_url, file_id
-            ))
-            .header("Authorization", access_token)
-            .send()
+            .send_authed(Repeat::Once, |client| client.patch(&url))
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => {
-                let res = res.json::<ShcFile>().await?;
-                Ok(res)
-            }
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.toggle_file_visibility(file_id).await;
-            }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        parse_json(res).await
     }
 
-    #[async_recursion]
-    pub async fn rename_file(
+    // sets the visibility instead of flipping it, a file that already has it is left alone
+    // returns the file and whether it had to be changed
+    pub async fn set_file_visibility(
         &mut self,
         file_id: &str,
-        new_name: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        is_public: bool,
+    ) -> Result<(ShcFile, bool), ShcError> {
+        let file = self.get_file_download_url(file_id).await?;
+        if file.is_public == is_public {
+            return Ok((file, false));
+        }
+        let file = self.toggle_file_visibility(file_id).await?;
+        Ok((file, true))
+    }
 
-        // self is &mut ApiClient?
+    pub async fn rename_file(&mut self, file_id: &str, new_name: &str) -> Result<(), ShcError> {
+        let url = format!("{}/api/files/rename/{}", self.api_base_url, file_id);
         let res = self
-            .client
-            .patch(format!(
-                "{}/api/files/rename/{}",
-                self.api_base_url, file_id
-            ))
-            .header("Authorization", access_token)
-            // meaning of &json!() ?
-            .json(&json!({
-                "name": new_name,
-            }))
-            .send()
+            .send_authed(Repeat::Once, |client| {
+                // meaning of &json!() ?
+                client.patch(&url).json(&json!({
+                    "name": new_name,
+                }))
+            })
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => Ok(()),
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.rename_file(file_id, new_name).await;
-            }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        expect_ok(res).await
     }
 
-    #[async_recursion]
     pub async fn add_file(
         &mut self,
         file_name: &str,
         mime_type: &str,
         file_size: u64,
-    ) -> Result<AddFileResponse, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        sha256: &str,
+        limits: &ShareLimits,
+    ) -> Result<AddFileResponse, ShcError> {
+        let url = format!("{}/api/files/add", self.api_base_url);
+        let res = self
+            .send_authed(Repeat::Once, |client| {
+                client.post(&url).json(&json!(
+                    {
+                        "file_name": file_name,
+                        "mime_type": mime_type,
+                        "file_size": file_size,
+                        "sha256": sha256,
+                        "expires_at": limits.expires_at,
+                        "max_downloads": limits.max_downloads,
+                    }
+                ))
+            })
+            .await?;
+
+        parse_json(res).await
+    }
 
+    // like add_file, but the new content goes to an existing file so its share link stays the same
+    pub async fn replace_file_content(
+        &mut self,
+        file_id: &str,
+        mime_type: &str,
+        file_size: u64,
+        sha256: &str,
+    ) -> Result<AddFileResponse, ShcError> {
+        let url = format!("{}/api/files/replace/{}", self.api_base_url, file_id);
         let res = self
-            .client
-            .post(format!("{}/api/files/add", self.api_base_url))
-            .header("Authorization", access_token)
-            .json(&json!(
-                {
-                    "file_name": file_name,
-                    "mime_type": mime_type,
-                    "file_size": file_size,
-                }
-            ))
-            .send()
+            .send_authed(Repeat::Once, |client| {
+                client.post(&url).json(&json!(
+                    {
+                        "mime_type": mime_type,
+                        "file_size": file_size,
+                        "sha256": sha256,
+                    }
+                ))
+            })
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => {
-                let res = res.json::<AddFileResponse>().await?;
-                Ok(res)
-            }
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.add_file(file_name, mime_type, file_size).await;
-            }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        parse_json(res).await
     }
 
-    #[async_recursion]
     pub async fn update_upload_status(
         &mut self,
         file_id: &str,
         upload_status: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+    ) -> Result<(), ShcError> {
+        let url = format!(
+            "{}/api/files/update-upload-status/{}",
+            self.api_base_url, file_id
+        );
+        let res = self
+            .send_authed(Repeat::Once, |client| {
+                client.patch(&url).json(&json!(
+                    {
+                        "upload_status": upload_status,
+                    }
+                ))
+            })
+            .await?;
+
+        expect_ok(res).await
+    }
 
+    pub async fn start_multipart_upload(
+        &mut self,
+        file_id: &str,
+        part_count: u64,
+    ) -> Result<MultipartUploadResponse, ShcError> {
+        let url = format!(
+            "{}/api/files/multipart/start/{}",
+            self.api_base_url, file_id
+        );
         let res = self
-            .client
-            .patch(format!(
-                "{}/api/files/update-upload-status/{}",
-                self.api_base_url, file_id
-            ))
-            .json(&json!(
-                {
-                    "upload_status": upload_status,
-                }
-            ))
-            .header("Authorization", access_token)
-            .send()
+            .send_authed(Repeat::Once, |client| {
+                client.post(&url).json(&json!(
+                    {
+                        "part_count": part_count,
+                    }
+                ))
+            })
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => Ok(()),
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.update_upload_status(file_id, upload_status).await;
-            }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        parse_json(res).await
+    }
+
+    pub async fn get_upload_part_url(
+        &mut self,
+        file_id: &str,
+        upload_id: &str,
+        part_number: u64,
+    ) -> Result<UploadPartResponse, ShcError> {
+        let url = format!(
+            "{}/api/files/multipart/part-url/{}?upload_id={}&part_number={}",
+            self.api_base_url, file_id, upload_id, part_number
+        );
+        let res = self
+            .send_authed(Repeat::Safe, |client| client.get(&url))
+            .await?;
+
+        parse_json(res).await
     }
 
-    #[async_recursion]
-    pub async fn get_file_download_url(
+    pub async fn complete_multipart_upload(
         &mut self,
         file_id: &str,
-    ) -> Result<ShcFile, Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
+        upload_id: &str,
+        parts: &[CompletedPart],
+    ) -> Result<(), ShcError> {
+        let url = format!(
+            "{}/api/files/multipart/complete/{}",
+            self.api_base_url, file_id
+        );
+        let res = self
+            .send_authed(Repeat::Once, |client| {
+                client.post(&url).json(&json!(
+                    {
+                        "upload_id": upload_id,
+                        "parts": parts,
+                    }
+                ))
+            })
+            .await?;
+
+        expect_ok(res).await
+    }
 
+    pub async fn get_file_download_url(&mut self, file_id: &str) -> Result<ShcFile, ShcError> {
+        // why and how we use format! here?
+        let url = format!("{}/api/files/{}", self.api_base_url, file_id);
         let res = self
-            .client
-            // why and how we use format! here?
-            .get(format!("{}/api/files/{}", self.api_base_url, file_id))
-            .header("Authorization", access_token)
-            .send()
+            .send_authed(Repeat::Safe, |client| client.get(&url))
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => {
-                let res = res.json::<ShcFile>().await?;
-                Ok(res)
-            }
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.get_file_download_url(file_id).await;
-            }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        parse_json(res).await
     }
 
-    #[async_recursion]
     pub async fn increment_download_count(
         &mut self,
         file_id: &str,
         // how to use Result Give some simple code?
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let access_token = self.user_config.user.access_token.as_ref().unwrap();
-
+    ) -> Result<(), ShcError> {
+        let url = format!(
+            "{}/api/files/increment-download-count/{}",
+            self.api_base_url, file_id
+        );
         let res = self
-            .client
-            .patch(format!(
-                "{}/api/files/increment-download-count/{}",
-                self.api_base_url, file_id
-            ))
-            .header("Authorization", access_token)
-            .send()
+            .send_authed(Repeat::Once, |client| client.patch(&url))
             .await?;
 
-        match res.status() {
-            reqwest::StatusCode::OK => Ok(()),
-            reqwest::StatusCode::UNAUTHORIZED => {
-                self.refresh_token().await?;
-                return self.increment_download_count(file_id).await;
-            }
-            _ => Err(Error::new(ErrorKind::Other, "Something went wrong").into()),
-        }
+        expect_ok(res).await
+    }
+}
+
+async fn parse_json<T: DeserializeOwned>(res: Response) -> Result<T, ShcError> {
+    match res.status() {
+        StatusCode::OK => Ok(res.json::<T>().await?),
+        _ => Err(ShcError::from_response(res).await),
+    }
+}
+
+async fn expect_ok(res: Response) -> Result<(), ShcError> {
+    match res.status() {
+        StatusCode::OK => Ok(()),
+        _ => Err(ShcError::from_response(res).await),
+    }
+}
+
+// whether the `exp` claim of a JWT access token is close, read without checking the signature
+// as the server does that, tokens that are not JWTs are only refreshed after a 401
+fn expires_soon(access_token: &str) -> bool {
+    let exp = access_token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok())
+        .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+        .and_then(|claims| claims.get("exp")?.as_i64());
+
+    match exp {
+        Some(exp) => exp - chrono::Utc::now().timestamp() < TOKEN_REFRESH_MARGIN_SECS,
+        None => false,
     }
 }
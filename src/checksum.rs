@@ -0,0 +1,68 @@
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::error::ShcError;
+
+// hex encoded SHA-256 of the file, sent with uploads and compared after downloads
+pub fn sha256_file(file_path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(file_path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// async wrapper so hashing a big file does not block the runtime
+pub async fn sha256_file_async(file_path: &Path) -> io::Result<String> {
+    let file_path = file_path.to_path_buf();
+    tokio::task::spawn_blocking(move || sha256_file(&file_path))
+        .await
+        .map_err(io::Error::other)?
+}
+
+// `expected` comes from the server, `actual` from the bytes we have
+pub fn check_sha256(expected: &str, actual: String) -> Result<(), ShcError> {
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(ShcError::ChecksumMismatch {
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+// hashes everything written through it, for streams that never end up in a file like stdout
+pub struct ChecksumWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    pub fn new(inner: W) -> Self {
+        ChecksumWriter {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn sha256(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
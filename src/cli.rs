@@ -1,4 +1,8 @@
-use clap::{arg, Command};
+use clap::{arg, value_parser, Arg, ArgAction, Command};
+use std::path::PathBuf;
+
+use crate::command::bulk::parse_age;
+use crate::file_slice::{parse_bytes, parse_lines};
 
 // are we defining the Command struct here and then returning it?
 pub fn cli() -> Command {
@@ -8,37 +12,195 @@ pub fn cli() -> Command {
         .arg_required_else_help(false)
         // allow_external_subcommands is used to allow subcommands to be passed as arguments ✅
         .allow_external_subcommands(true)
+        .arg(
+            arg!(--profile <NAME> "server profile to use")
+                .env("SHC_PROFILE")
+                .global(true),
+        )
+        .arg(
+            arg!(--output <FORMAT> "output format")
+                .value_parser(["table", "json", "ndjson", "csv"])
+                .default_value("table")
+                .global(true),
+        )
+        .arg(
+            arg!(--retries <N> "attempts after a failed request, 0 turns retries off")
+                .value_parser(value_parser!(u32))
+                .global(true),
+        )
+        .arg(
+            arg!(--timeout <SECS> "seconds to wait for an answer or for more of a download")
+                .value_parser(value_parser!(u64).range(1..))
+                .global(true),
+        )
+        .arg(
+            arg!(--"connect-timeout" <SECS> "seconds to wait for a connection")
+                .value_parser(value_parser!(u64).range(1..))
+                .global(true),
+        )
         .subcommand(Command::new("login").about("login to use shc"))
         .subcommand(
             Command::new("add")
-                .about("upload file")
-                .arg(arg!(<FILE> "file path to upload"))
+                .about("upload files")
+                .arg(arg!(<FILE>... "files, folders or glob patterns to upload, - to read from stdin"))
+                .arg(
+                    arg!(-j --jobs <N> "how many files to upload at the same time")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("4"),
+                )
+                .arg(
+                    arg!(--resume "continue an interrupted upload of FILE")
+                        .conflicts_with_all(["lines", "bytes"]),
+                )
+                .arg(arg!(--name <NAME> "name to upload the file with"))
+                .arg(arg!(--mime <TYPE> "mime type, guessed from the name or content if not set"))
+                .arg(
+                    arg!(--lines <RANGE> "only upload lines START:END, can be repeated")
+                        .value_parser(parse_lines)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    arg!(--bytes <RANGE> "only upload bytes START:END, can be repeated")
+                        .value_parser(parse_bytes)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    arg!(--encrypt "encrypt before upload, the key is only in the share link")
+                        .conflicts_with("resume"),
+                )
+                .arg(
+                    arg!(--watch "upload FILE again whenever it changes, the share link stays the same")
+                        .conflicts_with_all(["resume", "encrypt"]),
+                )
+                .args(visibility_args())
+                .arg(
+                    arg!(--"expires-in" <AGE> "let `shc prune` remove the file after AGE, e.g. 7d")
+                        .value_parser(parse_age),
+                )
+                .arg(
+                    arg!(--"max-downloads" <N> "let `shc prune` remove the file after N downloads")
+                        .value_parser(value_parser!(u64).range(1..)),
+                )
                 .arg_required_else_help(false),
         )
+        .subcommand(
+            Command::new("new")
+                .about("write a new text file in $EDITOR and upload it")
+                .arg(arg!(<NAME> "name of the new file")),
+        )
         .subcommand(
             Command::new("list")
                 .about("list all files")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .arg(arg!(<FILTER> "filter by filename").required(false))
+                .arg(
+                    arg!(--page <PAGE> "page to start at")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("1"),
+                )
+                .arg(
+                    arg!(--limit <LIMIT> "files per page")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("100"),
+                )
+                .arg(arg!(--all "fetch every page").conflicts_with("page")),
         )
         .subcommand(
             Command::new("remove")
                 .about("remove file")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .arg(arg!(<FILE> "file id, share link or filter by filename").required(false))
+                .arg(arg!(-y --yes "skip confirmation"))
+                .args(filter_args()),
         )
         .subcommand(
             Command::new("visibility")
-                .about("toggle file's visibility")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .about("toggle file's visibility, or set it with --public/--private")
+                .arg(arg!(<FILE> "file id, share link or filter by filename").required(false))
+                .arg(arg!(-y --yes "skip confirmation"))
+                .args(visibility_args())
+                .args(filter_args()),
         )
         .subcommand(
             Command::new("rename")
                 .about("rename file")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .arg(arg!(<FILE> "file id, share link or filter by filename").required(false))
+                .arg(arg!(-y --yes "skip confirmation"))
+                .arg(arg!(--"new-name" <NAME> "new filename, skips the editor")),
         )
         .subcommand(
             Command::new("get")
                 .about("download file")
-                .arg(arg!(<FILTER> "filter by filename").required(false)),
+                .arg(arg!(<FILE> "file id, share link or filter by filename").required(false))
+                .arg(arg!(-y --yes "skip confirmation"))
+                .arg(
+                    arg!(-o --out <PATH> "file or directory to download to, - for stdout")
+                        .short_alias('O')
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(--"on-conflict" <POLICY> "what to do if the file already exists")
+                        .value_parser(["skip", "overwrite", "rename"])
+                        .default_value("rename"),
+                )
+                .args(filter_args()),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("check that a local file matches an uploaded file")
+                .arg(arg!(<FILE> "file id, share link or filter by filename"))
+                .arg(arg!(<PATH> "local file to compare").value_parser(value_parser!(PathBuf))),
         )
+        .subcommand(
+            Command::new("sync")
+                .about("upload the new and changed files of a folder")
+                .arg(arg!(<DIR> "folder to sync").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--delete "also remove uploaded files that are not in the folder any more"))
+                .arg(arg!(--"dry-run" "only print what would be uploaded and removed"))
+                .arg(arg!(-y --yes "skip confirmation")),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("remove files that expired or reached their download limit")
+                .arg(arg!(-y --yes "skip confirmation"))
+                .arg(arg!(--"dry-run" "only list the files that would be removed")),
+        )
+        .subcommand(Command::new("ui").about("browse files in a full screen view, the default"))
         .subcommand(Command::new("logout").about("logout from shc"))
+        .subcommand(
+            Command::new("profile")
+                .about("manage server profiles")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("list all profiles"))
+                .subcommand(
+                    Command::new("set")
+                        .about("create or update the active profile")
+                        .arg(arg!(--"api-url" <URL> "backend API base URL"))
+                        .arg(arg!(--"frontend-url" <URL> "base URL of share links")),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("remove a profile")
+                        .arg(arg!(<NAME> "profile to remove")),
+                ),
+        )
+}
+
+// set the visibility instead of leaving it to the server or toggling it
+fn visibility_args() -> [Arg; 2] {
+    [
+        arg!(--public "make the file public"),
+        arg!(--private "make the file private").conflicts_with("public"),
+    ]
+}
+
+// with any of these set, remove, visibility and get act on every matching file instead of a picked one
+fn filter_args() -> [Arg; 2] {
+    [
+        arg!(--status <STATUS> "only files with this upload status").value_parser([
+            "uploaded",
+            "uploading",
+            "failed",
+        ]),
+        arg!(--"older-than" <AGE> "only files last changed more than AGE ago, e.g. 30d or 12h")
+            .value_parser(parse_age),
+    ]
 }
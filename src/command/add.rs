@@ -1,180 +1,1242 @@
 // why is the use of indiciatif crate?
-use indicatif::{ProgressBar, ProgressStyle};
+use console::style;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
 // what is meaning of min?
 use std::cmp::min;
 
+use std::io::SeekFrom;
+
 // what is meaning of Path?
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 // what is meaning of Duration?
 use std::time::Duration;
 
 // what is meaning of StreamExt?
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 // what is meaning of ReaderStream?
 use tokio_util::io::ReaderStream;
 
+use notify_debouncer_mini::new_debouncer;
+use notify_debouncer_mini::notify::RecursiveMode;
+
 use crate::api_client;
+use crate::checksum::sha256_file_async;
+use crate::consts::{MULTIPART_PART_SIZE, MULTIPART_UPLOAD_THRESHOLD, WATCH_DEBOUNCE_MS};
+use crate::encryption::{encrypt_stream, encrypted_sha256_async, encrypted_size, FileKey};
+use crate::error::ShcError;
+use crate::file_metadata::{self, FileMetadata};
+use crate::file_slice::{write_slice, FileRange};
+use crate::http;
+use crate::interrupt::{self, CleanupGuard, TempArtifact};
+use crate::models::{AddFileResponse, AddFileResult, AddFilesResult, CompletedPart, ShareLimits};
+use crate::output::{self, OutputFormat};
+use crate::tui::{spinner, track_multi_progress, track_progress};
+use crate::upload_journal::{self, file_stamp, JournalEntry};
+
+use crate::utils::{folder_walker, sniff_mime_type, zip_directory_recursive};
+
+#[derive(Default, Clone)]
+pub struct AddOptions {
+    // continue the interrupted upload of this file, fail if there is none
+    pub resume: bool,
+    // name and mime type to upload with instead of the ones derived from the path
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    // upload only these parts of the file
+    pub ranges: Vec<FileRange>,
+    // encrypt the file with a random key that only goes into the share link
+    pub encrypt: bool,
+    // visibility to set before the content is sent, the server's default if None
+    pub public: Option<bool>,
+    // --expires-in and --max-downloads, recorded locally for `shc prune`
+    pub limits: ShareLimits,
+    // id of an uploaded file to put the new content into instead of adding a file, see watch()
+    pub replace: Option<String>,
+}
+
+// where an upload draws its spinners and progress bar
+// a single upload draws them on its own, a batch gives every file a line in one MultiProgress
+#[derive(Clone, Default)]
+struct Progress {
+    multi: Option<MultiProgress>,
+    // bars of the batch, summed up into its total
+    bars: Arc<Mutex<Vec<ProgressBar>>>,
+}
+
+impl Progress {
+    fn spinner(&self, message: &'static str) -> ProgressBar {
+        if self.multi.is_some() {
+            // spinners of several files at once would only flicker, the file's bar is enough
+            return ProgressBar::hidden();
+        }
+        spinner(message)
+    }
+
+    fn bar(&self, total_size: u64) -> ProgressBar {
+        match &self.multi {
+            Some(multi) => {
+                let bar = ProgressBar::new(total_size).with_style(
+                    ProgressStyle::with_template(
+                        "{wide_msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec}",
+                    )
+                    .unwrap()
+                    .progress_chars("#>-"),
+                );
+                // the total stays the last line
+                let bar = multi.insert_from_back(1, bar);
+                self.bars.lock().unwrap().push(bar.clone());
+                bar
+            }
+            None => {
+                let bar = ProgressBar::new(total_size);
+                bar.set_style(
+                    ProgressStyle::with_template(
+                        "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec} \n",
+                    )
+                    .unwrap()
+                    .progress_chars("#>-"),
+                );
+                track_progress(&bar);
+                bar
+            }
+        }
+    }
+
+    fn println(&self, message: String) {
+        match &self.multi {
+            Some(multi) => multi.suspend(|| eprintln!("{}", message)),
+            None => eprintln!("{}", message),
+        }
+    }
+
+    fn update_total(&self, total: &ProgressBar) {
+        let bars = self.bars.lock().unwrap();
+        total.set_length(bars.iter().filter_map(ProgressBar::length).sum());
+        total.set_position(bars.iter().map(ProgressBar::position).sum());
+    }
+}
+
+// paths as given, with glob patterns like `logs/*.log` expanded
+// shells expand unquoted globs already, this covers quoted ones and shells that don't
+pub fn expand_paths(args: &[String]) -> Result<Vec<PathBuf>, ShcError> {
+    let mut file_paths = vec![];
+
+    for arg in args {
+        let path = PathBuf::from(arg);
+        if path.exists() {
+            file_paths.push(path);
+            continue;
+        }
+        if !arg.contains(['*', '?', '[']) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("File not found: {}", arg),
+            )
+            .into());
+        }
+
+        let matches = glob::glob(arg)
+            .map_err(|e| ShcError::Usage(format!("invalid pattern {}: {}", arg, e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ShcError::Io(e.into()))?;
+        if matches.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No files match {}", arg),
+            )
+            .into());
+        }
+        file_paths.extend(matches);
+    }
 
-use crate::utils::zip_directory_recursive;
+    Ok(file_paths)
+}
+
+// uploads several files, at most `jobs` at a time, and prints a summary with all share links
+pub async fn upload_files(
+    file_paths: &[PathBuf],
+    options: &AddOptions,
+    jobs: u64,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    if options.name.is_some() {
+        return Err(ShcError::Usage(
+            "--name can only be used when uploading a single file".to_string(),
+        ));
+    }
+
+    let _cleanup = CleanupGuard::new();
+    let multi = MultiProgress::new();
+    track_multi_progress(&multi);
+    let total = multi.add(ProgressBar::new(0));
+    total.set_style(
+        ProgressStyle::with_template(
+            "{msg} [{wide_bar:.green}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec}",
+        )
+        .unwrap()
+        .progress_chars("#>-"),
+    );
+    total.set_message(format!("0/{} files", file_paths.len()));
+    let progress = Progress {
+        multi: Some(multi),
+        bars: Arc::default(),
+    };
+
+    let semaphore = Arc::new(Semaphore::new(jobs as usize));
+    let mut uploads = JoinSet::new();
+    for (index, file_path) in file_paths.iter().enumerate() {
+        let file_path = file_path.clone();
+        let options = options.clone();
+        let progress = progress.clone();
+        let semaphore = semaphore.clone();
+        // every upload gets its own client, they share the connection pool
+        let mut api_client = api_client.clone();
+
+        uploads.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            let res = upload_one(&file_path, &options, &progress, &mut api_client).await;
+            (index, file_path, res)
+        });
+    }
+
+    let mut results = vec![];
+    let mut ticker = tokio::time::interval(Duration::from_millis(100));
+    loop {
+        tokio::select! {
+            res = uploads.join_next() => match res {
+                Some(res) => {
+                    results.push(res.map_err(std::io::Error::other)?);
+                    total.set_message(format!("{}/{} files", results.len(), file_paths.len()));
+                }
+                None => break,
+            },
+            _ = ticker.tick() => progress.update_total(&total),
+        }
+    }
+    total.finish_and_clear();
+    results.sort_by_key(|(index, _, _)| *index);
+
+    let rows: Vec<AddFilesResult> = results
+        .iter()
+        .map(|(_, file_path, res)| {
+            let path = file_path.to_string_lossy().to_string();
+            match res {
+                Ok(result) => AddFilesResult {
+                    path,
+                    file_id: Some(result.file_id.clone()),
+                    file_name: Some(result.file_name.clone()),
+                    share_link: Some(result.share_link.clone()),
+                    error: None,
+                },
+                Err(e) => AddFilesResult {
+                    path,
+                    file_id: None,
+                    file_name: None,
+                    share_link: None,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect();
+
+    if format.is_human() {
+        print_summary(&rows);
+    } else {
+        output::print_many(format, &rows)?;
+    }
+
+    // the exit code follows the first failure, all of them are in the summary
+    match results.into_iter().find_map(|(_, _, res)| res.err()) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn print_summary(rows: &[AddFilesResult]) {
+    let path_width = rows.iter().map(|row| row.path.len()).max().unwrap_or(0);
+    let failed = rows.iter().filter(|row| row.error.is_some()).count();
+
+    println!();
+    for row in rows {
+        match (&row.share_link, &row.error) {
+            (Some(share_link), _) => println!(
+                "{} {:<path_width$}  {}",
+                style("✔").green(),
+                row.path,
+                share_link
+            ),
+            (None, error) => println!(
+                "{} {:<path_width$}  {}",
+                style("✘").red(),
+                row.path,
+                style(error.as_deref().unwrap_or_default()).red()
+            ),
+        }
+    }
+    println!("\n{} added, {} failed", rows.len() - failed, failed);
+}
+
+// uploads everything read from stdin, e.g. `cargo test 2>&1 | shc add - --name test.log`
+pub async fn upload_stdin(
+    options: &AddOptions,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    if options.resume {
+        return Err(ShcError::Usage(
+            "--resume cannot be used when uploading from stdin".to_string(),
+        ));
+    }
+
+    let _cleanup = CleanupGuard::new();
+    let pb = spinner("Reading stdin...");
+
+    // the upload needs the size up front, so stdin is spooled to a temp file first
+    let (spool_file, spool_path) = tempfile::NamedTempFile::new()?
+        .keep()
+        .map_err(|e| e.error)?;
+    let spool = TempArtifact::new(spool_path);
+    let mut spool_file = tokio::fs::File::from_std(spool_file);
+    let mut stdin = tokio::io::stdin();
+    let res = tokio::select! {
+        res = tokio::io::copy(&mut stdin, &mut spool_file) => res,
+        _ = interrupt::cancelled() => return Err(ShcError::Interrupted),
+    };
+    pb.finish_and_clear();
+    res?;
+    spool_file.flush().await?;
+    drop(spool_file);
+
+    // named stdin.txt or stdin.bin unless --name is given
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None if sniff_mime_type(spool.path())? == "text/plain" => "stdin.txt".to_string(),
+        None => "stdin.bin".to_string(),
+    };
+    let options = AddOptions {
+        name: Some(name),
+        ..options.clone()
+    };
+
+    upload_file(spool.path(), &options, format, api_client).await
+}
 
 pub async fn upload_file(
     file_path: &Path,
+    options: &AddOptions,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    let _cleanup = CleanupGuard::new();
+    let result = upload_one(file_path, options, &Progress::default(), api_client).await?;
+
+    if format.is_human() {
+        print!(
+            "\n{} added successfully\nShcFile Link: {}\n",
+            result.file_name, result.share_link
+        );
+        if !options.limits.is_empty() {
+            let expires_at = options.limits.expires_at.as_ref();
+            let max_downloads = options.limits.max_downloads;
+            let limits: Vec<String> = expires_at
+                .map(|expires_at| format!("at {}", expires_at))
+                .into_iter()
+                .chain(max_downloads.map(|count| format!("after {} downloads", count)))
+                .collect();
+            println!(
+                "Expires {}, `shc prune` removes it then",
+                limits.join(" or ")
+            );
+        }
+    } else {
+        output::print_one(format, &result)?;
+    }
+
+    Ok(())
+}
+
+// uploads the file or folder, then puts the new content into the same file whenever it changes
+// so the share link never changes, runs until Ctrl-C
+pub async fn watch(
+    file_path: &Path,
+    options: &AddOptions,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(WATCH_DEBOUNCE_MS), move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(std::io::Error::other)?;
+    // editors often save by replacing the file, that only shows up in the folder around it
+    let (watched, mode) = match file_path.is_dir() {
+        true => (file_path, RecursiveMode::Recursive),
+        false => (
+            file_path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or(Path::new(".")),
+            RecursiveMode::NonRecursive,
+        ),
+    };
+    debouncer
+        .watcher()
+        .watch(watched, mode)
+        .map_err(std::io::Error::other)?;
+
+    let mut snapshot = watch_snapshot(file_path);
+    let result = {
+        let _cleanup = CleanupGuard::new();
+        upload_one(file_path, options, &Progress::default(), api_client).await?
+    };
+    if format.is_human() {
+        print!(
+            "\n{} added successfully\nShcFile Link: {}\n",
+            result.file_name, result.share_link
+        );
+        eprintln!(
+            "Watching {} for changes, press Ctrl-C to stop",
+            file_path.display()
+        );
+    } else {
+        output::print_one(format, &result)?;
+    }
+
+    let options = AddOptions {
+        replace: Some(result.file_id),
+        ..options.clone()
+    };
+    while let Some(res) = rx.recv().await {
+        if let Err(e) = res {
+            eprintln!("{} {}", style("warning:").yellow().bold(), e);
+            continue;
+        }
+        // events also come for other files in the folder and for ignored files
+        let current = watch_snapshot(file_path);
+        if current == snapshot {
+            continue;
+        }
+        snapshot = current;
+
+        let res = {
+            let _cleanup = CleanupGuard::new();
+            upload_one(file_path, &options, &Progress::default(), api_client).await
+        };
+        match res {
+            Ok(result) if format.is_human() => println!(
+                "{} Updated {} at {}",
+                style("✔").green(),
+                result.file_name,
+                chrono::Local::now().format("%H:%M:%S")
+            ),
+            Ok(result) => output::print_one(format, &result)?,
+            Err(ShcError::Interrupted) => return Err(ShcError::Interrupted),
+            // the next change is uploaded again, no reason to stop watching
+            Err(e) => eprintln!("{} {}", style("✘").red(), style(e).red()),
+        }
+    }
+
+    Ok(())
+}
+
+// every file an upload of the path includes, with its size and modification time
+fn watch_snapshot(file_path: &Path) -> Vec<(PathBuf, Option<(u64, u64)>)> {
+    if !file_path.is_dir() {
+        return vec![(file_path.to_path_buf(), file_stamp(file_path))];
+    }
+
+    folder_walker(file_path)
+        .filter_map(Result::ok)
+        .map(ignore::DirEntry::into_path)
+        .filter(|path| path.is_file())
+        .map(|path| {
+            let stamp = file_stamp(&path);
+            (path, stamp)
+        })
+        .collect()
+}
+
+// uploads one file with its own spinners and bar and returns the result instead of printing it
+pub async fn upload_path(
+    file_path: &Path,
+    options: &AddOptions,
+    api_client: &mut api_client::ApiClient,
+) -> Result<AddFileResult, ShcError> {
+    upload_one(file_path, options, &Progress::default(), api_client).await
+}
+
+// a file, a folder or the --lines/--bytes ranges of a file
+async fn upload_one(
+    file_path: &Path,
+    options: &AddOptions,
+    progress: &Progress,
+    api_client: &mut api_client::ApiClient,
+) -> Result<AddFileResult, ShcError> {
+    let result = if options.ranges.is_empty() {
+        upload(file_path, options, progress, api_client).await?
+    } else {
+        upload_slice(file_path, options, progress, api_client).await?
+    };
+
+    if !options.limits.is_empty() && options.replace.is_none() {
+        let metadata = FileMetadata {
+            profile: api_client.profile().to_string(),
+            file_name: result.file_name.clone(),
+            limits: options.limits.clone(),
+        };
+        file_metadata::insert(&result.file_id, metadata)?;
+    }
+
+    Ok(result)
+}
+
+async fn upload(
+    file_path: &Path,
+    options: &AddOptions,
+    progress: &Progress,
     api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<AddFileResult, ShcError> {
     if !file_path.exists() {
-        println!("ShcFile or Folder does not exist");
-        return Ok(());
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "ShcFile or Folder does not exist",
+        )
+        .into());
     }
 
     let is_dir = file_path.is_dir();
-    let file_path = if is_dir {
-        let pb = ProgressBar::new_spinner();
-
-        pb.enable_steady_tick(Duration::from_millis(200));
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                .unwrap()
-                .tick_chars("/|\\- "),
-        );
+    if !is_dir {
+        discard_conflicting_upload(file_path, options, progress, api_client).await?;
+    }
+    // encrypted files are always sent in one request, the parts of a resumed upload
+    // would need the key that only the share link of the finished upload has
+    // so is replaced content, parts can only be uploaded for a newly added file
+    if !is_dir && !options.encrypt && options.replace.is_none() {
+        let resumable = upload_journal::get(file_path, api_client.profile()).is_some();
+        let file_size = std::fs::metadata(file_path)?.len();
+        if options.resume || resumable || file_size >= MULTIPART_UPLOAD_THRESHOLD {
+            return upload_file_in_parts(file_path, options, progress, api_client).await;
+        }
+    }
 
-        pb.set_message("Compressing folder...");
+    // taken from the folder, the zip only has a temporary name
+    let file_name = upload_name(file_path, options);
+    let zip_file = if is_dir {
+        let pb = progress.spinner("Compressing folder...");
         let zip_file_path = zip_directory_recursive(file_path, 30 * 1024 * 1024)?;
         pb.finish_and_clear();
-        zip_file_path
+        // the zip is deleted once it is dropped, whether the upload worked or not
+        Some(TempArtifact::new(zip_file_path))
     } else {
-        file_path.to_path_buf()
+        None
     };
+    let file_path = zip_file.as_ref().map_or(file_path, TempArtifact::path);
 
-    let file_name = file_path.file_name().unwrap().to_str().unwrap();
-    let mime_type = mime_guess::from_path(&file_path).first_or_octet_stream();
-    let file = tokio::fs::File::open(&file_path)
-        .await
-        .expect("Cannot open input file for HTTPS read");
-    let total_size = file
-        .metadata()
-        .await
-        .expect("Cannot determine input file size for HTTPS read")
-        .len();
-    let client = reqwest::Client::new();
-
-    let pb = ProgressBar::new_spinner();
+    let key = options.encrypt.then(FileKey::generate);
+    let mime_type = match key {
+        // the real type would tell the server something about the content
+        Some(_) => "application/octet-stream".to_string(),
+        None => upload_mime_type(&file_name, file_path, options)?,
+    };
+    let file_size = tokio::fs::metadata(file_path).await?.len();
+    let total_size = match key {
+        Some(_) => encrypted_size(file_size),
+        None => file_size,
+    };
+    let http = api_client.http();
+    let client = http.transfer_client();
 
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
+    let pb = progress.spinner("Computing checksum...");
+    // the server stores the checksum of what it receives
+    let sha256 = match &key {
+        Some(key) => encrypted_sha256_async(file_path, key).await?,
+        None => sha256_file_async(file_path).await?,
+    };
 
     pb.set_message("Preparing for upload...");
 
-    let res = api_client
-        .add_file(file_name, mime_type.as_ref(), total_size)
-        .await?;
+    let res = match &options.replace {
+        Some(file_id) => {
+            api_client
+                .replace_file_content(file_id, &mime_type, total_size, &sha256)
+                .await?
+        }
+        None => {
+            api_client
+                .add_file(&file_name, &mime_type, total_size, &sha256, &options.limits)
+                .await?
+        }
+    };
+    let is_public = set_visibility(&res, options, &pb, api_client).await?;
     pb.finish_and_clear();
 
     let file_id = res.file_id;
     let file_name = res.file_name;
     let upload_url = res.upload_url;
 
-    let mut uploaded = 0;
-
-    let mut reader_stream = ReaderStream::new(file);
-    let bar = ProgressBar::new(total_size);
-    bar.set_style(
-        ProgressStyle::with_template(
-            "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec} \n",
-        )
-        .unwrap()
-        .progress_chars("#>-"),
-    );
-    let res = api_client.update_upload_status(&file_id, "uploading").await;
-
-    match res {
-        Ok(_) => {}
-        Err(_) => {
-            // TODO: better error handling
-            println!("Failed to upload file");
-            std::process::exit(1);
-        }
-    }
+    let bar = progress.bar(total_size);
+    api_client
+        .update_upload_status(&file_id, "uploading")
+        .await?;
 
     bar.reset_eta();
     bar.set_message(format!("Uploading {}", file_name));
-    let async_stream = async_stream::stream! {
-        while let Some(chunk) = reader_stream.next().await {
-            if let Ok(chunk) = &chunk {
-                let new = min(uploaded + (chunk.len() as u64), total_size);
-                uploaded = new;
-                bar.set_position(new);
-                if uploaded >= total_size {
-                    //TODO: fix this
-                        bar.finish_and_clear();
+    // a retried upload starts over, every attempt reads the file again
+    let body = || {
+        let file_path = file_path.to_path_buf();
+        let key = key.clone();
+        let bar = bar.clone();
+        bar.set_position(0);
+        let async_stream = async_stream::stream! {
+            let file = match tokio::fs::File::open(&file_path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    yield Err(e);
+                    return;
                 }
+            };
+            let mut reader_stream: Pin<
+                Box<dyn Stream<Item = std::io::Result<bytes::Bytes>> + Send + Sync>,
+            > = match &key {
+                Some(key) => Box::pin(encrypt_stream(file, file_size, key.clone())),
+                None => Box::pin(ReaderStream::new(file)),
+            };
+            let mut uploaded = 0;
+            while let Some(chunk) = reader_stream.next().await {
+                if let Ok(chunk) = &chunk {
+                    let new = min(uploaded + (chunk.len() as u64), total_size);
+                    uploaded = new;
+                    bar.set_position(new);
+                    if uploaded >= total_size {
+                        //TODO: fix this
+                            bar.finish_and_clear();
+                    }
+                }
+                yield chunk;
             }
-            yield chunk;
+        };
+        reqwest::Body::wrap_stream(async_stream)
+    };
+    let request = || {
+        client
+            .put(&upload_url)
+            .body(body())
+            .header("Content-Type", &mime_type)
+            .header("Content-Length", total_size.to_string())
+    };
+
+    let res = tokio::select! {
+        res = http::send(&http, request) => res,
+        _ = interrupt::cancelled() => Err(ShcError::Interrupted),
+    };
+
+    let pb = progress.spinner("Adding file...");
+
+    let result = match res {
+        Ok(res) if res.status() == reqwest::StatusCode::OK => {
+            api_client.update_upload_status(&file_id, "uploaded").await
+        }
+        res => {
+            let err = match res {
+                Ok(res) => ShcError::from_response(res).await,
+                Err(e) => e,
+            };
+            // the upload error is what matters, marking the file is best effort
+            let _ = api_client
+                .update_upload_status(file_id.as_str(), "failed")
+                .await;
+            Err(err)
         }
     };
+    pb.finish_and_clear();
 
-    let res = client
-        .put(upload_url)
-        .body(reqwest::Body::wrap_stream(async_stream))
-        .header("Content-Type", mime_type.as_ref())
-        .header("Content-Length", total_size.to_string())
-        .send()
-        .await?;
+    result?;
 
-    let pb = ProgressBar::new_spinner();
+    let mut share_link = api_client.share_link(&file_id);
+    if let Some(key) = &key {
+        // the fragment is never sent to the server, only whoever has the link can decrypt
+        share_link = format!("{}#{}", share_link, key.to_fragment());
+    }
 
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
+    Ok(AddFileResult {
+        share_link,
+        file_id,
+        file_name,
+        is_public,
+        sha256,
+    })
+}
 
-    pb.set_message("Adding file...");
+// uploads only the --lines/--bytes ranges of the file, named like `main.rs#L120-180`
+async fn upload_slice(
+    file_path: &Path,
+    options: &AddOptions,
+    progress: &Progress,
+    api_client: &mut api_client::ApiClient,
+) -> Result<AddFileResult, ShcError> {
+    if file_path.is_dir() {
+        return Err(ShcError::Usage(
+            "--lines and --bytes can only be used with files".to_string(),
+        ));
+    }
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let res = api_client.update_upload_status(&file_id, "uploaded").await;
-            pb.finish_and_clear();
-            match res {
-                Ok(_) => {
-                    print!(
-                        "\n{} added successfully\nShcFile Link: https:/shc-frontend-two.vercel.app/share/{}\n",
-                        file_name, file_id
-                    );
-                }
-                Err(_) => {
-                    print!("Failed to add file");
-                }
-            }
+    let (slice, suffix) = write_slice(file_path, &options.ranges)?;
+    let file_name = upload_name(file_path, options);
+    let mut options = AddOptions {
+        ranges: vec![],
+        ..options.clone()
+    };
+    if options.mime_type.is_none() {
+        // the suffix hides the extension from mime_guess
+        options.mime_type = Some(upload_mime_type(&file_name, file_path, &options)?);
+    }
+    if options.name.is_none() {
+        options.name = Some(format!("{}{}", file_name, suffix));
+    }
+
+    upload(slice.path(), &options, progress, api_client).await
+}
+
+// applies --public or --private right after the file was added, before any of its content
+// is sent, so the file is never reachable with the wrong visibility
+async fn set_visibility(
+    res: &AddFileResponse,
+    options: &AddOptions,
+    pb: &ProgressBar,
+    api_client: &mut api_client::ApiClient,
+) -> Result<bool, ShcError> {
+    match options.public {
+        Some(public) if public != res.is_public => {
+            pb.set_message("Changing visibility...");
+            Ok(api_client
+                .toggle_file_visibility(&res.file_id)
+                .await?
+                .is_public)
         }
-        _ => {
+        _ => Ok(res.is_public),
+    }
+}
+
+fn upload_name(file_path: &Path, options: &AddOptions) -> String {
+    if let Some(name) = &options.name {
+        return name.clone();
+    }
+    if file_path.is_dir() {
+        // a folder is uploaded as a zip named after it, `shc add .` names it after the current folder
+        let folder = std::fs::canonicalize(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+        return format!("{}.zip", folder.file_name().unwrap().to_string_lossy());
+    }
+    file_path.file_name().unwrap().to_string_lossy().to_string()
+}
+
+// --mime if given, otherwise guessed from the name and then from the content
+fn upload_mime_type(
+    file_name: &str,
+    file_path: &Path,
+    options: &AddOptions,
+) -> Result<String, ShcError> {
+    if let Some(mime_type) = &options.mime_type {
+        return Ok(mime_type.clone());
+    }
+    match mime_guess::from_path(file_name).first() {
+        Some(mime_type) => Ok(mime_type.to_string()),
+        None => Ok(sniff_mime_type(file_path)?.to_string()),
+    }
+}
+
+// uploads the file part by part, every finished part is recorded in the upload journal
+// so a failed upload continues where it stopped when `shc add` is run again
+async fn upload_file_in_parts(
+    file_path: &Path,
+    options: &AddOptions,
+    progress: &Progress,
+    api_client: &mut api_client::ApiClient,
+) -> Result<AddFileResult, ShcError> {
+    let client = api_client.http().transfer_client();
+    // a temp file, like the stdin spool or a --lines slice, is gone by the time the command runs
+    // again, so its upload can't be resumed
+    let journaled = !interrupt::is_temp_artifact(file_path);
+
+    let entry = match upload_journal::get(file_path, api_client.profile()) {
+        Some(entry) => {
+            progress.println(format!(
+                "Resuming upload of {} ({}/{} parts done)",
+                entry.file_name,
+                entry.parts.len(),
+                entry.part_count()
+            ));
+            // it was marked failed when it stopped
+            api_client
+                .update_upload_status(&entry.file_id, "uploading")
+                .await?;
+            entry
+        }
+        None if options.resume => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No interrupted upload found for {}", file_path.display()),
+            )
+            .into());
+        }
+        None => {
+            let (file_size, modified) = file_stamp(file_path).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "ShcFile or Folder does not exist",
+                )
+            })?;
+            let file_name = upload_name(file_path, options);
+            let mime_type = upload_mime_type(&file_name, file_path, options)?;
+
+            let pb = progress.spinner("Computing checksum...");
+            let sha256 = sha256_file_async(file_path).await?;
+
+            pb.set_message("Preparing for upload...");
+
             let res = api_client
-                .update_upload_status(file_id.as_str(), "failed")
-                .await;
+                .add_file(&file_name, &mime_type, file_size, &sha256, &options.limits)
+                .await?;
+            let is_public = set_visibility(&res, options, &pb, api_client).await?;
+            api_client
+                .update_upload_status(&res.file_id, "uploading")
+                .await?;
+            let part_count = file_size.div_ceil(MULTIPART_PART_SIZE).max(1);
+            let multipart = api_client
+                .start_multipart_upload(&res.file_id, part_count)
+                .await?;
             pb.finish_and_clear();
-            match res {
-                Ok(_) => {
-                    print!("Failed to upload file");
-                }
 
-                Err(_) => {
-                    print!("Something went wrong!");
+            let entry = JournalEntry {
+                profile: api_client.profile().to_string(),
+                file_size,
+                modified,
+                file_id: res.file_id,
+                file_name: res.file_name,
+                mime_type,
+                is_public,
+                limits: options.limits.clone(),
+                sha256,
+                upload_id: multipart.upload_id,
+                part_size: MULTIPART_PART_SIZE,
+                parts: vec![],
+            };
+            if journaled {
+                upload_journal::insert(file_path, entry.clone())?;
+            }
+            entry
+        }
+    };
+
+    let file_id = entry.file_id.clone();
+    let file_name = entry.file_name.clone();
+    let total_size = entry.file_size;
+    let mut file = tokio::fs::File::open(file_path).await?;
+
+    let bar = progress.bar(total_size);
+    bar.set_position(entry.uploaded_bytes());
+    bar.reset_eta();
+    bar.set_message(format!("Uploading {}", file_name));
+
+    let mut entry = entry;
+    for part_number in 1..=entry.part_count() {
+        if entry
+            .parts
+            .iter()
+            .any(|part| part.part_number == part_number)
+        {
+            continue;
+        }
+
+        let start = (part_number - 1) * entry.part_size;
+        let len = entry.part_size.min(total_size - start);
+        let mut chunk = vec![0; len as usize];
+        file.seek(SeekFrom::Start(start)).await?;
+        file.read_exact(&mut chunk).await?;
+
+        // an interrupted upload keeps its journal entry so it can be resumed
+        let res = tokio::select! {
+            res = upload_part(&client, &entry, part_number, chunk, &bar, api_client) => res,
+            _ = interrupt::cancelled() => Err(ShcError::Interrupted),
+        };
+        match res {
+            Ok(etag) => {
+                entry.parts.push(CompletedPart { part_number, etag });
+                if journaled {
+                    upload_journal::insert(file_path, entry.clone())?;
                 }
             }
+            Err(e) => {
+                bar.abandon();
+                // the upload error is what matters, marking the file is best effort
+                let _ = api_client.update_upload_status(&file_id, "failed").await;
+                if journaled {
+                    progress.println(format!(
+                        "Upload interrupted, {}/{} parts done. Run `shc add {}` again to resume.",
+                        entry.parts.len(),
+                        entry.part_count(),
+                        file_path.display()
+                    ));
+                }
+                return Err(e);
+            }
         }
     }
+    bar.finish_and_clear();
+
+    let pb = progress.spinner("Adding file...");
 
-    // Delete the zip file if it was created by the app
-    if is_dir {
-        std::fs::remove_file(&file_path)?;
+    entry.parts.sort_by_key(|part| part.part_number);
+    let res = api_client
+        .complete_multipart_upload(&file_id, &entry.upload_id, &entry.parts)
+        .await;
+    if res.is_err() {
+        pb.finish_and_clear();
+        let _ = api_client.update_upload_status(&file_id, "failed").await;
     }
+    res?;
+    api_client
+        .update_upload_status(&file_id, "uploaded")
+        .await?;
+    if journaled {
+        upload_journal::remove(file_path)?;
+    }
+    pb.finish_and_clear();
+
+    Ok(AddFileResult {
+        share_link: api_client.share_link(&file_id),
+        file_id,
+        file_name,
+        is_public: entry.is_public,
+        sha256: entry.sha256,
+    })
+}
 
+// the options an interrupted upload was started with can't change once its parts are sent,
+// when they differ the upload starts over instead, or fails with --resume
+async fn discard_conflicting_upload(
+    file_path: &Path,
+    options: &AddOptions,
+    progress: &Progress,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    let Some(entry) = upload_journal::get(file_path, api_client.profile()) else {
+        return Ok(());
+    };
+    let Some(conflict) = resume_conflict(&entry, options) else {
+        return Ok(());
+    };
+    if options.resume {
+        return Err(ShcError::Usage(format!(
+            "cannot resume the upload of {}, {}",
+            file_path.display(),
+            conflict
+        )));
+    }
+
+    progress.println(format!(
+        "Starting the upload of {} over, {}",
+        file_path.display(),
+        conflict
+    ));
+    // best effort, the interrupted upload is left behind either way
+    let _ = api_client
+        .update_upload_status(&entry.file_id, "failed")
+        .await;
+    upload_journal::remove(file_path)?;
     Ok(())
 }
+
+// only options given again are compared, `shc add <file>` alone continues with the old ones
+// the expiry is relative to the first attempt, so a resumed upload keeps the one it started with
+fn resume_conflict(entry: &JournalEntry, options: &AddOptions) -> Option<&'static str> {
+    let differs = |given: Option<&String>, started: &str| given.is_some_and(|g| g != started);
+    if options.encrypt {
+        Some("it was started without --encrypt")
+    } else if options.replace.is_some() {
+        Some("it was started as a new file")
+    } else if differs(options.name.as_ref(), &entry.file_name) {
+        Some("it was started with another --name")
+    } else if differs(options.mime_type.as_ref(), &entry.mime_type) {
+        Some("it was started with another --mime")
+    } else if options
+        .public
+        .is_some_and(|public| public != entry.is_public)
+    {
+        Some("it was started with the other visibility")
+    } else if options
+        .limits
+        .max_downloads
+        .is_some_and(|max| Some(max) != entry.limits.max_downloads)
+        || (options.limits.expires_at.is_some() && entry.limits.expires_at.is_none())
+    {
+        Some("it was started with other --expires-in or --max-downloads")
+    } else {
+        None
+    }
+}
+
+// sends one part to its presigned url and returns the ETag the storage assigned to it
+async fn upload_part(
+    client: &reqwest::Client,
+    entry: &JournalEntry,
+    part_number: u64,
+    chunk: Vec<u8>,
+    bar: &ProgressBar,
+    api_client: &mut api_client::ApiClient,
+) -> Result<String, ShcError> {
+    let upload_url = api_client
+        .get_upload_part_url(&entry.file_id, &entry.upload_id, part_number)
+        .await?
+        .upload_url;
+
+    let start = bar.position();
+    let len = chunk.len();
+    let chunk = bytes::Bytes::from(chunk);
+    // the part is sent again from its start if the attempt fails
+    let body = || {
+        let progress = bar.clone();
+        let chunk = chunk.clone();
+        progress.set_position(start);
+        let async_stream = async_stream::stream! {
+            for offset in (0..len).step_by(64 * 1024) {
+                let end = min(offset + 64 * 1024, len);
+                progress.set_position(start + end as u64);
+                yield Ok::<_, std::io::Error>(chunk.slice(offset..end));
+            }
+        };
+        reqwest::Body::wrap_stream(async_stream)
+    };
+
+    let res = http::send(&api_client.http(), || {
+        client
+            .put(&upload_url)
+            .body(body())
+            .header("Content-Length", len.to_string())
+    })
+    .await;
+
+    match res {
+        Ok(res) if res.status().is_success() => Ok(res
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .unwrap_or_default()
+            .to_string()),
+        Ok(res) => {
+            bar.set_position(start);
+            Err(ShcError::from_response(res).await)
+        }
+        Err(e) => {
+            bar.set_position(start);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{self, Reply, TestServer};
+    use serde_json::json;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // the stub fails part 2 until fail_part is cleared
+    async fn multipart_server(fail_part: Arc<AtomicBool>) -> TestServer {
+        TestServer::start(move |request| {
+            let path = request.path.as_str();
+            if path.starts_with("/api/files/add") {
+                Reply::json(json!({
+                    "upload_url": "",
+                    "file_id": "resumed-file",
+                    "file_name": "big.bin",
+                    "is_public": false,
+                }))
+            } else if path.starts_with("/api/files/multipart/start/") {
+                Reply::json(json!({ "upload_id": "upload-1" }))
+            } else if path.starts_with("/api/files/multipart/part-url/") {
+                let part_number = path.rsplit('=').next().unwrap();
+                let host = request.header("Host").unwrap();
+                Reply::json(
+                    json!({ "upload_url": format!("http://{}/part/{}", host, part_number) }),
+                )
+            } else if path == "/part/2" && fail_part.load(Ordering::SeqCst) {
+                Reply::status(500)
+            } else if let Some(part_number) = path.strip_prefix("/part/") {
+                Reply::status(200).header("ETag", &format!("etag-{}", part_number))
+            } else {
+                Reply::json(json!({}))
+            }
+        })
+        .await
+    }
+
+    fn statuses(requests: &[test_server::Request]) -> Vec<String> {
+        requests
+            .iter()
+            .filter(|request| request.path.starts_with("/api/files/update-upload-status/"))
+            .map(|request| {
+                request.json()["upload_status"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    // a file with an interrupted upload of it in the journal
+    async fn interrupted_upload(api_client: &mut api_client::ApiClient) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&vec![7; 2 * MULTIPART_PART_SIZE as usize])
+            .unwrap();
+        let res = upload_file_in_parts(
+            file.path(),
+            &AddOptions::default(),
+            &Progress::default(),
+            api_client,
+        )
+        .await;
+        assert!(res.is_err());
+        assert!(upload_journal::get(file.path(), api_client.profile()).is_some());
+        file
+    }
+
+    #[tokio::test]
+    async fn failed_part_is_resumed_without_uploading_finished_parts() {
+        let fail_part = Arc::new(AtomicBool::new(true));
+        let server = multipart_server(fail_part.clone()).await;
+        let mut api_client = test_server::api_client(&server, 0);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let size = 2 * MULTIPART_PART_SIZE as usize + 1024;
+        file.write_all(&vec![7; size]).unwrap();
+        let options = AddOptions::default();
+
+        let first =
+            upload_file_in_parts(file.path(), &options, &Progress::default(), &mut api_client)
+                .await;
+        assert!(first.is_err());
+        assert_eq!(statuses(&server.requests()), ["uploading", "failed"]);
+        let entry = upload_journal::get(file.path(), api_client.profile()).unwrap();
+        assert_eq!(entry.parts.len(), 1);
+        assert_eq!(entry.parts[0].part_number, 1);
+        assert_eq!(entry.parts[0].etag, "etag-1");
+
+        fail_part.store(false, Ordering::SeqCst);
+        let sent_before = server.requests().len();
+        upload_file_in_parts(file.path(), &options, &Progress::default(), &mut api_client)
+            .await
+            .unwrap();
+
+        let second = &server.requests()[sent_before..];
+        assert_eq!(statuses(second), ["uploading", "uploaded"]);
+        let paths: Vec<_> = second
+            .iter()
+            .filter(|request| request.method == "PUT")
+            .map(|request| request.path.as_str())
+            .collect();
+        assert_eq!(paths, ["/part/2", "/part/3"]);
+        assert!(!second
+            .iter()
+            .any(|request| request.path.starts_with("/api/files/add")));
+
+        let complete = second
+            .iter()
+            .position(|request| request.path.starts_with("/api/files/multipart/complete/"))
+            .unwrap();
+        let last_put = second
+            .iter()
+            .rposition(|request| request.method == "PUT")
+            .unwrap();
+        assert!(last_put < complete);
+        let parts = second[complete].json()["parts"].clone();
+        assert_eq!(
+            parts,
+            json!([
+                { "part_number": 1, "etag": "etag-1" },
+                { "part_number": 2, "etag": "etag-2" },
+                { "part_number": 3, "etag": "etag-3" },
+            ])
+        );
+
+        assert!(upload_journal::get(file.path(), api_client.profile()).is_none());
+    }
+
+    #[tokio::test]
+    async fn upload_with_other_options_starts_over() {
+        let server = multipart_server(Arc::new(AtomicBool::new(true))).await;
+        let mut api_client = test_server::api_client(&server, 0);
+        let file = interrupted_upload(&mut api_client).await;
+
+        let options = AddOptions {
+            name: Some("other.bin".to_string()),
+            resume: true,
+            ..AddOptions::default()
+        };
+        let res = discard_conflicting_upload(
+            file.path(),
+            &options,
+            &Progress::default(),
+            &mut api_client,
+        )
+        .await;
+        assert!(matches!(res, Err(ShcError::Usage(_))));
+        assert!(upload_journal::get(file.path(), api_client.profile()).is_some());
+
+        // the same name as before is no conflict
+        let options = AddOptions {
+            name: Some("big.bin".to_string()),
+            ..AddOptions::default()
+        };
+        discard_conflicting_upload(file.path(), &options, &Progress::default(), &mut api_client)
+            .await
+            .unwrap();
+        assert!(upload_journal::get(file.path(), api_client.profile()).is_some());
+
+        let sent_before = server.requests().len();
+        let options = AddOptions {
+            public: Some(true),
+            ..AddOptions::default()
+        };
+        discard_conflicting_upload(file.path(), &options, &Progress::default(), &mut api_client)
+            .await
+            .unwrap();
+        assert!(upload_journal::get(file.path(), api_client.profile()).is_none());
+        assert_eq!(statuses(&server.requests()[sent_before..]), ["failed"]);
+    }
+
+    #[tokio::test]
+    async fn upload_of_a_temp_file_is_not_journaled() {
+        let server = multipart_server(Arc::new(AtomicBool::new(true))).await;
+        let mut api_client = test_server::api_client(&server, 0);
+
+        let (mut file, path) = tempfile::NamedTempFile::new().unwrap().keep().unwrap();
+        let temp_file = TempArtifact::new(path);
+        file.write_all(&vec![7; 2 * MULTIPART_PART_SIZE as usize])
+            .unwrap();
+        let res = upload_file_in_parts(
+            temp_file.path(),
+            &AddOptions::default(),
+            &Progress::default(),
+            &mut api_client,
+        )
+        .await;
+
+        assert!(res.is_err());
+        assert!(upload_journal::get(temp_file.path(), api_client.profile()).is_none());
+        assert_eq!(statuses(&server.requests()), ["uploading", "failed"]);
+    }
+}
@@ -1,9 +1,12 @@
-use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::time::Duration;
+use std::io::IsTerminal;
 
-use crate::consts;
+use crate::error::ShcError;
+use crate::http::HttpSettings;
+use crate::models::{LoginResult, LogoutResult};
+use crate::output::{self, OutputFormat};
+use crate::tui::spinner;
 use crate::user_config::UserConfig;
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -15,30 +18,20 @@ struct OtpResponse {
     id: String,
 }
 
-pub async fn login(user_config: &mut UserConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
+pub async fn login(user_config: &mut UserConfig, http: HttpSettings) -> Result<(), ShcError> {
+    let client = http.api_client();
 
     let name = dialoguer::Input::<String>::new()
         .with_prompt("Name")
-        .interact_text()
-        .unwrap();
+        .interact_text()?;
 
     let email = dialoguer::Input::<String>::new()
         .with_prompt("Email")
-        .interact_text()
-        .unwrap();
+        .interact_text()?;
 
-    let pb = ProgressBar::new_spinner();
-
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
-    pb.set_message("Sending OTP...");
-    let _ = client
-        .post(format!("{}/auth/otp", consts::SHC_BACKEND_API_BASE_URL))
+    let pb = spinner("Sending OTP...");
+    let res = client
+        .post(format!("{}/auth/otp", user_config.api_base_url()))
         .json(&json!({
             "name": name,
             "email": email
@@ -46,27 +39,19 @@ pub async fn login(user_config: &mut UserConfig) -> Result<(), Box<dyn std::erro
         .send()
         .await?;
 
-    // print!("{:?}", res);
-
     pb.finish_and_clear();
+    if !res.status().is_success() {
+        return Err(ShcError::from_response(res).await);
+    }
 
     let otp = dialoguer::Input::<String>::new()
         .with_prompt("Check your mail for OTP, Enter")
-        .interact_text()
-        .unwrap();
-
-    let pb = ProgressBar::new_spinner();
+        .interact_text()?;
 
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
-    pb.set_message("Verifying OTP...");
+    let pb = spinner("Verifying OTP...");
 
     let res = client
-        .post(format!("{}/auth/login", consts::SHC_BACKEND_API_BASE_URL))
+        .post(format!("{}/auth/login", user_config.api_base_url()))
         .json(&json!(
             {
                 "name": name,
@@ -79,7 +64,7 @@ pub async fn login(user_config: &mut UserConfig) -> Result<(), Box<dyn std::erro
 
     pb.finish_and_clear();
     if res.status().is_success() {
-        println!("Login Successfull");
+        eprintln!("Login Successfull");
         let res: OtpResponse = res.json().await?;
         user_config.user.email = Some(res.email);
         user_config.user.name = Some(res.name);
@@ -88,25 +73,52 @@ pub async fn login(user_config: &mut UserConfig) -> Result<(), Box<dyn std::erro
         user_config.user.refresh_token = Some(res.refresh_token);
         user_config.save();
     } else {
-        println!("Login Failed");
+        return Err(ShcError::from_response(res).await);
     }
     Ok(())
 }
 
 pub async fn check_for_api_key(
     user_config: &mut UserConfig,
-) -> Result<(), Box<dyn std::error::Error>> {
+    http: HttpSettings,
+) -> Result<(), ShcError> {
     match user_config.user.access_token.as_ref() {
         Some(_) => {}
         None => {
-            println!("Please login first");
-            login(user_config).await?;
+            if !std::io::stdin().is_terminal() {
+                return Err(ShcError::AuthExpired);
+            }
+            eprintln!("Please login first");
+            login(user_config, http).await?;
         }
     }
     Ok(())
 }
 
-pub fn logout(user_config: &mut UserConfig) {
+// prints who is logged in now, the prompts of `login` already went to stderr
+pub fn print_login(user_config: &UserConfig, format: OutputFormat) -> Result<(), ShcError> {
+    if format.is_human() {
+        return Ok(());
+    }
+    output::print_one(
+        format,
+        &LoginResult {
+            profile: user_config.profile.clone(),
+            email: user_config.user.email.clone(),
+        },
+    )
+}
+
+pub fn logout(user_config: &mut UserConfig, format: OutputFormat) -> Result<(), ShcError> {
     user_config.clear();
-    println!("Logged out");
+    match format.is_human() {
+        true => println!("Logged out"),
+        false => output::print_one(
+            format,
+            &LogoutResult {
+                profile: user_config.profile.clone(),
+            },
+        )?,
+    }
+    Ok(())
 }
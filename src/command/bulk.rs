@@ -0,0 +1,239 @@
+use chrono::{DateTime, Utc};
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_stream::StreamExt;
+
+use crate::api_client::ApiClient;
+use crate::consts::{BULK_JOBS, DEFAULT_PAGE_SIZE};
+use crate::error::ShcError;
+use crate::models::ShcFile;
+use crate::output::{self, OutputFormat};
+use crate::tui::{confirm, select_files, spinner, track_progress};
+use crate::utils::format_bytes;
+
+// `--status` and `--older-than`, with any of them set a command acts on every matching file
+#[derive(Default, Clone)]
+pub struct FileFilter {
+    pub status: Option<String>,
+    pub older_than: Option<chrono::Duration>,
+}
+
+impl FileFilter {
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none() && self.older_than.is_none()
+    }
+
+    fn matches(&self, file: &ShcFile) -> bool {
+        if let Some(status) = &self.status {
+            if file.upload_status != *status {
+                return false;
+            }
+        }
+        if let Some(older_than) = self.older_than {
+            let Ok(updated_at) = DateTime::parse_from_rfc3339(&file.updated_at) else {
+                return false;
+            };
+            if Utc::now().signed_duration_since(updated_at) < older_than {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// value parser for `--older-than 30d`, units are s, m, h, d and w
+pub fn parse_age(arg: &str) -> Result<chrono::Duration, String> {
+    let arg = arg.trim();
+    let unit_at = arg
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| "missing unit, e.g. 30d or 12h".to_string())?;
+    let (count, unit) = arg.split_at(unit_at);
+    let count: i64 = count
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", count))?;
+
+    let age = match unit {
+        "s" => chrono::Duration::try_seconds(count),
+        "m" => chrono::Duration::try_minutes(count),
+        "h" => chrono::Duration::try_hours(count),
+        "d" => chrono::Duration::try_days(count),
+        "w" => chrono::Duration::try_weeks(count),
+        _ => return Err(format!("unknown unit `{}`, use s, m, h, d or w", unit)),
+    };
+    age.ok_or_else(|| format!("`{}` is too long", arg))
+}
+
+// the files a bulk command acts on, every file matching `filter` and the name filter `target`
+// or without filters the ones picked in the picker
+pub async fn select_targets(
+    target: &str,
+    prompt: &str,
+    filter: &FileFilter,
+    api_client: &mut ApiClient,
+) -> Result<Vec<ShcFile>, ShcError> {
+    if filter.is_empty() {
+        return select_files(target, prompt, api_client).await;
+    }
+
+    let pb = spinner("Fetching files...");
+
+    let mut files = vec![];
+    let stream = api_client.list_all_files(target, DEFAULT_PAGE_SIZE);
+    tokio::pin!(stream);
+    while let Some(file) = stream.next().await {
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                pb.finish_and_clear();
+                return Err(e);
+            }
+        };
+        if filter.matches(&file) {
+            files.push(file);
+        }
+    }
+    pb.finish_and_clear();
+
+    Ok(files)
+}
+
+// lists every file about to be changed, then asks once for all of them
+pub fn confirm_files(files: &[ShcFile], prompt: &str, assume_yes: bool) -> Result<(), ShcError> {
+    print_files(files);
+    confirm(prompt, assume_yes)
+}
+
+// one line per file on stderr with its size, status and age
+pub fn print_files(files: &[ShcFile]) {
+    let date_formatter = timeago::Formatter::new();
+    let name_width = files
+        .iter()
+        .map(|file| file.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for file in files {
+        let time_ago = DateTime::parse_from_rfc3339(&file.updated_at)
+            .map(|updated_at| date_formatter.convert_chrono(updated_at, Utc::now()))
+            .unwrap_or_default();
+        eprintln!(
+            "  {:<name_width$}  {:>10}  {:<9}  {}",
+            file.name,
+            format_bytes(file.size),
+            file.upload_status,
+            style(time_ago).dim()
+        );
+    }
+}
+
+// runs `action` for every file, at most BULK_JOBS at a time, with a bar counting finished files
+// a failing file does not stop the others, results are in the order of `files`
+pub async fn run<T, F, Fut>(
+    files: Vec<ShcFile>,
+    message: &str,
+    api_client: &ApiClient,
+    action: F,
+) -> Result<Vec<(ShcFile, Result<T, ShcError>)>, ShcError>
+where
+    T: Send + 'static,
+    F: Fn(ShcFile, ApiClient) -> Fut,
+    Fut: Future<Output = Result<T, ShcError>> + Send + 'static,
+{
+    let bar = ProgressBar::new(files.len() as u64);
+    bar.set_style(
+        ProgressStyle::with_template("{spinner:.green} {msg} [{wide_bar:.cyan/blue}] {pos}/{len}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    bar.enable_steady_tick(Duration::from_millis(200));
+    bar.set_message(message.to_string());
+    track_progress(&bar);
+
+    let semaphore = Arc::new(Semaphore::new(BULK_JOBS));
+    let mut tasks = JoinSet::new();
+    for (index, file) in files.iter().enumerate() {
+        // every task gets its own client, they share the connection pool
+        let task = action(file.clone(), api_client.clone());
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            (index, task.await)
+        });
+    }
+
+    let mut results = vec![];
+    while let Some(res) = tasks.join_next().await {
+        results.push(res.map_err(std::io::Error::other)?);
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+    results.sort_by_key(|(index, _)| *index);
+
+    Ok(files
+        .into_iter()
+        .zip(results)
+        .map(|(file, (_, res))| (file, res))
+        .collect())
+}
+
+// prints what happened to every file, `describe` turns a result into a line like "Removed x"
+// machine readable output only carries the results, failures are always listed on stderr
+// returns the first error so the exit code shows that something failed
+pub fn report<T: Serialize>(
+    results: Vec<(ShcFile, Result<T, ShcError>)>,
+    format: OutputFormat,
+    describe: impl Fn(&T) -> String,
+) -> Result<(), ShcError> {
+    let total = results.len();
+    let mut done = vec![];
+    let mut first_error = None;
+
+    for (file, res) in results {
+        match res {
+            Ok(result) => {
+                if format.is_human() {
+                    match total {
+                        1 => println!("{}", describe(&result)),
+                        _ => println!("{} {}", style("✔").green(), describe(&result)),
+                    }
+                }
+                done.push(result);
+            }
+            // a single file fails like any other command
+            Err(e) if total == 1 => return Err(e),
+            Err(e) => {
+                let line = format!("{} {}: {}", style("✘").red(), file.name, style(&e).red());
+                match format.is_human() {
+                    true => println!("{}", line),
+                    false => eprintln!("{}", line),
+                }
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if format.is_human() {
+        if total > 1 {
+            println!("\n{} done, {} failed", done.len(), total - done.len());
+        }
+    } else {
+        match (total, done.as_slice()) {
+            (1, [result]) => output::print_one(format, result)?,
+            _ => output::print_many(format, &done)?,
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
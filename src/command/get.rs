@@ -1,115 +1,760 @@
-use dialoguer::Confirm;
 use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use std::cmp::min;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio_stream::StreamExt;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::checksum::{check_sha256, sha256_file_async, ChecksumWriter};
+use crate::command::bulk::{self, FileFilter};
+use crate::encryption::{resume_point, DecryptWriter, FileKey};
+use crate::error::ShcError;
+use crate::http::{self, HttpSettings};
+use crate::interrupt::{self, CleanupGuard};
+use crate::models::{DownloadResult, ShcFile};
+use crate::output::{self, OutputFormat};
+use crate::tui::{confirm, spinner, track_progress};
+use crate::utils::format_bytes;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OnConflict {
+    // leave the existing file alone and don't download
+    Skip,
+    Overwrite,
+    // download next to it as "name (1).ext"
+    Rename,
+}
+
+impl OnConflict {
+    pub fn from_arg(policy: &str) -> OnConflict {
+        match policy {
+            "skip" => OnConflict::Skip,
+            "overwrite" => OnConflict::Overwrite,
+            _ => OnConflict::Rename,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GetOptions {
+    // file or directory to download to, `-` for stdout, the current directory if None
+    pub out: Option<PathBuf>,
+    pub on_conflict: OnConflict,
+}
+
+enum Destination {
+    Stdout,
+    File(PathBuf),
+}
 
 pub async fn download_file(
-    search: &str,
+    target: &str,
+    filter: &FileFilter,
+    assume_yes: bool,
+    options: &GetOptions,
+    format: OutputFormat,
     api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
+) -> Result<(), ShcError> {
+    let client = api_client.http().transfer_client();
+    // files uploaded with `--encrypt` have their key in the fragment of the share link
+    let key = FileKey::from_share_link(target)?;
+
+    let prompt = "Which files do you want to download?";
+    let files = bulk::select_targets(target, prompt, filter, api_client).await?;
+    match files.as_slice() {
+        [] => {
+            match format.is_human() {
+                true => println!("No files found."),
+                false => output::print_many::<DownloadResult>(format, &[])?,
+            }
+            return Ok(());
+        }
+        [file] => confirm(&format!("Download \"{}\"?", file.name), assume_yes)?,
+        files => {
+            // several files can only go into a directory
+            let into_dir = options.out.as_deref().is_none_or(|out| {
+                out.is_dir() || out.to_string_lossy().ends_with(std::path::MAIN_SEPARATOR)
+            });
+            if !into_dir {
+                return Err(ShcError::Usage(
+                    "-o has to be a directory when downloading several files".to_string(),
+                ));
+            }
+            let prompt = format!("Download these {} files?", files.len());
+            bulk::confirm_files(files, &prompt, assume_yes)?;
+        }
+    }
 
-    let pb = ProgressBar::new_spinner();
+    // paths claimed by the downloads of this run, two files with the same name never share one
+    let taken = Arc::new(Mutex::new(vec![]));
+    let results = match files.as_slice() {
+        [file] => {
+            let bar = ProgressBar::new(0);
+            bar.set_style(
+                ProgressStyle::with_template(
+                    "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec} \n",
+                )
+                .unwrap()
+                .progress_chars("#>-"),
+            );
+            track_progress(&bar);
+            let res = download_one(
+                file.clone(),
+                key,
+                options.clone(),
+                taken,
+                bar,
+                client,
+                api_client.clone(),
+            )
+            .await;
+            // stdout only carries the file itself, nothing else is printed
+            if options.out.as_deref() == Some(Path::new("-")) {
+                return res.map(|_| ());
+            }
+            vec![(file.clone(), res)]
+        }
+        _ => {
+            let message = "Downloading files...";
+            bulk::run(files, message, api_client, |file, api_client| {
+                let bar = ProgressBar::hidden();
+                download_one(
+                    file,
+                    None,
+                    options.clone(),
+                    taken.clone(),
+                    bar,
+                    client.clone(),
+                    api_client,
+                )
+            })
+            .await?
+        }
+    };
 
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
-    pb.set_message("Fetching files...");
-    let res = api_client.list_files(search).await?;
+    bulk::report(results, format, |result| match result.skipped {
+        true => format!(
+            "Skipped {}, {} already exists",
+            result.file_name, result.path
+        ),
+        false => format!("Downloaded {}", result.path),
+    })
+}
 
-    pb.finish_and_clear();
+// downloads one file to where the options point, `taken` are the paths other downloads use
+async fn download_one(
+    file: ShcFile,
+    key: Option<FileKey>,
+    options: GetOptions,
+    taken: Arc<Mutex<Vec<PathBuf>>>,
+    bar: ProgressBar,
+    client: reqwest::Client,
+    mut api_client: api_client::ApiClient,
+) -> Result<DownloadResult, ShcError> {
+    let key = key.as_ref();
+    let client = &client;
+    let http = &api_client.http();
+    let file_id = file.id.clone();
+    let shc_file = match file.download_url {
+        Some(_) => file,
+        None => {
+            let pb = spinner("Preparing for download...");
+            let res = api_client.get_file_download_url(&file_id).await;
 
-    if res.results.is_empty() {
-        println!("No files found.");
-        return Ok(());
+            pb.finish_and_clear();
+
+            res?
+        }
+    };
+
+    let download_url = shc_file.download_url;
+    let expected_sha256 = shc_file.sha256;
+    // the name comes from the server, never let it point outside the destination directory
+    let file_name = Path::new(&shc_file.name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_id.clone());
+
+    let dest_path = {
+        let mut taken = taken.lock().unwrap();
+        let dest_path = match destination(options.out.as_deref(), &file_name)? {
+            Destination::File(path) if taken.contains(&path) => Some(free_path(&path, &taken)),
+            Destination::File(path) if path.exists() => match options.on_conflict {
+                OnConflict::Skip => {
+                    return Ok(DownloadResult {
+                        file_id,
+                        file_name,
+                        path: path.to_string_lossy().to_string(),
+                        size: 0,
+                        skipped: true,
+                    });
+                }
+                OnConflict::Overwrite => Some(path),
+                OnConflict::Rename => Some(free_path(&path, &taken)),
+            },
+            Destination::File(path) => Some(path),
+            Destination::Stdout => None,
+        };
+        taken.extend(dest_path.clone());
+        dest_path
+    };
+
+    let download_url = download_url
+        .ok_or_else(|| ShcError::Deserialize("server did not return a download url".to_string()))?;
+
+    bar.set_message(format!("Downloading... {}", file_name));
+
+    let _cleanup = CleanupGuard::new();
+    let downloaded = match &dest_path {
+        Some(dest_path) => {
+            // written next to the destination and renamed into place once complete,
+            // so an existing file is never left half overwritten
+            let part_path = part_path(dest_path);
+            let origin = PartOrigin {
+                file_id: file_id.clone(),
+                size: shc_file.size,
+                updated_at: shc_file.updated_at.clone(),
+            };
+            let res =
+                download_resumable(client, http, &download_url, &part_path, &origin, key, &bar)
+                    .await;
+            match &res {
+                // a wrong key or a modified file fails the same way on every attempt
+                Err(ShcError::Io(e)) if e.kind() == std::io::ErrorKind::InvalidData => {
+                    remove_part(&part_path);
+                }
+                Err(_) if part_path.exists() => {
+                    bar.abandon();
+                    eprintln!("Download stopped, run the same command again to resume it.");
+                }
+                _ => {}
+            }
+            res?;
+
+            // the part file is hashed as a whole since a resumed download was written in pieces
+            // the checksum of an encrypted file is of its ciphertext, decrypting it already
+            // checked every chunk
+            if let (Some(expected), None) = (&expected_sha256, &key) {
+                let actual = sha256_file_async(&part_path).await?;
+                if let Err(e) = check_sha256(expected, actual) {
+                    remove_part(&part_path);
+                    return Err(e);
+                }
+            }
+            std::fs::rename(&part_path, dest_path)?;
+            let _ = std::fs::remove_file(origin_path(&part_path));
+            std::fs::metadata(dest_path)?.len()
+        }
+        None => {
+            let res = send_download_request(client, http, &download_url, 0).await?;
+            if !res.status().is_success() {
+                return Err(ShcError::from_response(res).await);
+            }
+            let stdout = std::io::BufWriter::new(std::io::stdout());
+            match key {
+                Some(key) => {
+                    let mut out = ChecksumWriter::new(DecryptWriter::new(stdout, key, 0));
+                    let written = write_body(res, &mut out, 0, http.timeout, &bar).await?;
+                    // a reader that stopped early, like `head`, only got part of the file
+                    if !written.reader_gone {
+                        if let Some(expected) = &expected_sha256 {
+                            check_sha256(expected, out.sha256())?;
+                        }
+                        out.into_inner().finish()?;
+                    }
+                    written.size
+                }
+                None => {
+                    let mut out = ChecksumWriter::new(stdout);
+                    let written = write_body(res, &mut out, 0, http.timeout, &bar).await?;
+                    if !written.reader_gone {
+                        if let Some(expected) = &expected_sha256 {
+                            check_sha256(expected, out.sha256())?;
+                        }
+                    }
+                    written.size
+                }
+            }
+        }
+    };
+    bar.finish_and_clear();
+
+    // try to increment download count but ignore the result
+    let _ = api_client.increment_download_count(&file_id).await;
+
+    Ok(DownloadResult {
+        file_id,
+        path: dest_path.map_or("-".to_string(), |dest_path| {
+            dest_path.to_string_lossy().to_string()
+        }),
+        file_name,
+        size: downloaded,
+        skipped: false,
+    })
+}
+
+// downloads into part_path, continuing after whatever an earlier attempt left there
+// if it was downloading the same file, a dropped connection is retried from the current
+// length of the part file, with a key the part file holds the decrypted file
+async fn download_resumable(
+    client: &reqwest::Client,
+    http: &HttpSettings,
+    download_url: &str,
+    part_path: &Path,
+    origin: &PartOrigin,
+    key: Option<&FileKey>,
+    bar: &ProgressBar,
+) -> Result<u64, ShcError> {
+    claim_part(part_path, origin)?;
+    let mut retries = 0;
+
+    loop {
+        let err = match download_range(client, http, download_url, part_path, key, bar).await {
+            Ok(downloaded) => return Ok(downloaded),
+            Err(e) if http::is_transient(&e) && retries < http.retries => e,
+            Err(e) => return Err(e),
+        };
+
+        bar.suspend(|| {
+            eprintln!(
+                "Connection lost ({}), retrying {}/{}...",
+                err,
+                retries + 1,
+                http.retries
+            )
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(http::backoff(retries)) => {}
+            _ = interrupt::cancelled() => return Err(ShcError::Interrupted),
+        }
+        retries += 1;
     }
+}
 
-    let selection = shc_file_input(&res.results, "Which file do you want to download?");
+// requests everything after what the part file already has and appends it
+// returns the size of the complete file on the server
+async fn download_range(
+    client: &reqwest::Client,
+    http: &HttpSettings,
+    download_url: &str,
+    part_path: &Path,
+    key: Option<&FileKey>,
+    bar: &ProgressBar,
+) -> Result<u64, ShcError> {
+    let part_len = std::fs::metadata(part_path).map_or(0, |metadata| metadata.len());
+    // an encrypted file continues at the start of a chunk, `position` is that chunk
+    let (kept, offset, position) = match key {
+        Some(_) => resume_point(part_len),
+        None => (part_len, part_len, 0),
+    };
+    let res = send_download_request(client, http, download_url, offset).await?;
 
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure?")
-        .default(false)
-        .interact()
-        .unwrap();
+    let (file, start, position) = match res.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            // a server answering a different range than asked for would corrupt the file
+            if content_range_start(&res) != Some(offset) {
+                return Err(ShcError::Deserialize(format!(
+                    "server did not continue the download at byte {}",
+                    offset
+                )));
+            }
+            let file = OpenOptions::new().append(true).open(part_path)?;
+            file.set_len(kept)?;
+            (file, offset, position)
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+            // everything was downloaded already, only the rename was missing
+            // an encrypted file always fetches its last chunk again, so it never ends up here
+            if key.is_none() && content_range_total(&res) == Some(offset) {
+                return Ok(offset);
+            }
+            // the part file is left over from a different file, start over
+            std::fs::remove_file(part_path)?;
+            return Box::pin(download_range(
+                client,
+                http,
+                download_url,
+                part_path,
+                key,
+                bar,
+            ))
+            .await;
+        }
+        // the server ignored the Range header, start over
+        status if status.is_success() => (File::create(part_path)?, 0, 0),
+        _ => return Err(ShcError::from_response(res).await),
+    };
 
-    if !confirm {
-        println!("Aborted");
-        return Ok(());
-    } else {
-        let file_id = res.results[selection].id.clone();
-        let pb = ProgressBar::new_spinner();
+    if start > 0 {
+        bar.suspend(|| eprintln!("Resuming download at {}", format_bytes(kept)));
+    }
+    let out = std::io::BufWriter::new(file);
+    match key {
+        Some(key) => {
+            let mut out = DecryptWriter::new(out, key, position);
+            let written = write_body(res, &mut out, start, http.timeout, bar).await?;
+            out.finish()?;
+            Ok(written.size)
+        }
+        None => Ok(write_body(res, &mut { out }, start, http.timeout, bar)
+            .await?
+            .size),
+    }
+}
 
-        pb.enable_steady_tick(Duration::from_millis(200));
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                .unwrap()
-                .tick_chars("/|\\- "),
-        );
-        pb.set_message("Preparing for download...");
-        let res = api_client.get_file_download_url(&file_id).await;
+async fn send_download_request(
+    client: &reqwest::Client,
+    http: &HttpSettings,
+    download_url: &str,
+    offset: u64,
+) -> Result<reqwest::Response, ShcError> {
+    let request = || {
+        let req = client.get(download_url);
+        match offset {
+            0 => req,
+            _ => req.header(RANGE, format!("bytes={}-", offset)),
+        }
+    };
 
-        pb.finish_and_clear();
+    tokio::select! {
+        res = http::send(http, request) => res,
+        _ = interrupt::cancelled() => Err(ShcError::Interrupted),
+    }
+}
 
-        let shc_file = match res {
-            Ok(shc_file) => shc_file,
+// how far write_body got
+struct Written {
+    // bytes written including the ones of earlier attempts
+    size: u64,
+    // the reader of stdout went away before the end, e.g. `shc get <id> -O - | head`
+    reader_gone: bool,
+}
+
+// streams the response body into `out`, `start` is the number of bytes written by earlier attempts
+// fails if nothing arrives for `timeout` or the body ends before its Content-Length
+async fn write_body(
+    res: reqwest::Response,
+    out: &mut impl Write,
+    start: u64,
+    timeout: Duration,
+    bar: &ProgressBar,
+) -> Result<Written, ShcError> {
+    let content_length = res.content_length();
+    let total_size = start + content_length.unwrap_or(0);
+    let mut downloaded = start;
+    bar.set_length(total_size);
+    bar.set_position(start);
+    bar.reset_eta();
+
+    let mut stream = res.bytes_stream();
+    loop {
+        let item = tokio::select! {
+            item = tokio::time::timeout(timeout, stream.next()) => match item {
+                Ok(item) => item,
+                Err(_) => {
+                    out.flush()?;
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!("no data received for {}s", timeout.as_secs()),
+                    )
+                    .into());
+                }
+            },
+            _ = interrupt::cancelled() => return Err(ShcError::Interrupted),
+        };
+        let Some(item) = item else {
+            break;
+        };
+        // the bytes received before a dropped connection are kept for the retry
+        let chunk = match item {
+            Ok(chunk) => chunk,
             Err(e) => {
-                println!("Error: {}", e);
-                std::process::exit(1);
+                out.flush()?;
+                return Err(e.into());
             }
         };
+        match out.write_all(&chunk) {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                return Ok(Written {
+                    size: downloaded,
+                    reader_gone: true,
+                })
+            }
+            res => res?,
+        }
+        downloaded += chunk.len() as u64;
+        bar.set_position(min(downloaded, total_size));
+    }
 
-        let download_url = shc_file.download_url;
-        let file_name = shc_file.name;
+    let reader_gone = match out.flush() {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => true,
+        res => {
+            res?;
+            false
+        }
+    };
+    if !reader_gone && content_length.is_some_and(|len| downloaded - start != len) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "connection closed after {} of {} bytes",
+                downloaded, total_size
+            ),
+        )
+        .into());
+    }
+    Ok(Written {
+        size: downloaded,
+        reader_gone,
+    })
+}
 
-        let mut downloaded: u64 = 0;
+fn part_path(dest_path: &Path) -> PathBuf {
+    let mut part_name = dest_path.file_name().unwrap_or_default().to_os_string();
+    part_name.push(".part");
+    dest_path.with_file_name(part_name)
+}
 
-        let res = client.get(download_url.unwrap()).send().await?;
-        let total_size = downloaded + res.content_length().unwrap_or(0);
-        let bar = ProgressBar::new(total_size);
-        let file = File::create(&file_name)
-            .map_err(|_| format!("Failed to create file '{file_name}'"))
-            .unwrap();
+// the file a part file holds the start of, kept next to it as `<name>.part.json`
+// a part of another file with the same name, or of content replaced since, is not continued
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct PartOrigin {
+    file_id: String,
+    size: u64,
+    updated_at: String,
+}
 
-        let mut out: Box<dyn Write + Send> = Box::new(std::io::BufWriter::new(file));
+fn origin_path(part_path: &Path) -> PathBuf {
+    let mut origin_name = part_path.file_name().unwrap_or_default().to_os_string();
+    origin_name.push(".json");
+    part_path.with_file_name(origin_name)
+}
 
-        bar.set_style(
-        ProgressStyle::with_template(
-            "{msg}\n{spinner:.green} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta}) {bytes_per_sec} \n",
-        )
+// keeps the part file only if it was downloading `origin`, otherwise the download starts over
+fn claim_part(part_path: &Path, origin: &PartOrigin) -> std::io::Result<()> {
+    let origin_path = origin_path(part_path);
+    let same_file = std::fs::read(&origin_path)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<PartOrigin>(&json).ok())
+        .is_some_and(|found| found == *origin);
+    if same_file {
+        return Ok(());
+    }
+    match std::fs::remove_file(part_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    std::fs::write(origin_path, serde_json::to_vec(origin)?)
+}
+
+fn remove_part(part_path: &Path) {
+    let _ = std::fs::remove_file(part_path);
+    let _ = std::fs::remove_file(origin_path(part_path));
+}
+
+// start offset from a `Content-Range: bytes 100-999/1000` header
+fn content_range_start(res: &reqwest::Response) -> Option<u64> {
+    res.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+// total size from a `Content-Range: bytes */1000` header
+fn content_range_total(res: &reqwest::Response) -> Option<u64> {
+    res.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+// `out` may be a file, an existing directory or a path ending in `/` for a new directory
+fn destination(out: Option<&Path>, file_name: &str) -> Result<Destination, ShcError> {
+    let out = match out {
+        Some(out) if out.as_os_str() == "-" => return Ok(Destination::Stdout),
+        Some(out) => out,
+        None => return Ok(Destination::File(PathBuf::from(file_name))),
+    };
+
+    if out.is_dir() {
+        return Ok(Destination::File(out.join(file_name)));
+    }
+    if out.to_string_lossy().ends_with(std::path::MAIN_SEPARATOR) {
+        std::fs::create_dir_all(out)?;
+        return Ok(Destination::File(out.join(file_name)));
+    }
+    Ok(Destination::File(out.to_path_buf()))
+}
+
+// first of "name (1).ext", "name (2).ext", ... that does not exist yet and is not taken
+fn free_path(path: &Path, taken: &[PathBuf]) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists() && !taken.contains(path))
         .unwrap()
-        .progress_chars("#>-"),
-    );
-        bar.reset_eta();
-        bar.set_message(format!("Downloading... {}", file_name));
-
-        let mut stream = res.bytes_stream();
-        while let Some(item) = stream.next().await {
-            let chunk = item.map_err(|_| "Error while downloading.").unwrap();
-            out.write_all(&chunk)
-                .map_err(|_| "Error while writing to output.")
-                .unwrap();
-            let new = min(downloaded + (chunk.len() as u64), total_size);
-            downloaded = new;
-            bar.set_position(new);
-        }
-        bar.finish_and_clear();
-        println!("Downloaded {}", file_name);
-
-        // try to increment download count but ignore the result
-        match api_client.increment_download_count(&file_id).await {
-            Ok(_) => {}
-            Err(_) => {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, TestServer};
+
+    fn content() -> Vec<u8> {
+        (0..1000).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn settings() -> HttpSettings {
+        HttpSettings {
+            retries: 2,
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn origin(file_id: &str) -> PartOrigin {
+        PartOrigin {
+            file_id: file_id.to_string(),
+            size: 1000,
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    // a part file left by an earlier attempt at `file_id`
+    fn leave_part(part_path: &Path, file_id: &str, contents: &[u8]) {
+        std::fs::write(part_path, contents).unwrap();
+        let origin = serde_json::to_vec(&origin(file_id)).unwrap();
+        std::fs::write(origin_path(part_path), origin).unwrap();
+    }
+
+    async fn download(server: &TestServer, part_path: &Path) -> Result<u64, ShcError> {
+        let client = settings().transfer_client();
+        let url = format!("{}/file", server.url);
+        let bar = ProgressBar::hidden();
+        let origin = origin("file");
+        download_resumable(&client, &settings(), &url, part_path, &origin, None, &bar).await
+    }
+
+    // answers Range requests like a storage server would
+    fn range_reply(content: &[u8], range: Option<&str>) -> Reply {
+        let Some(start) = range.and_then(|range| range.strip_prefix("bytes=")) else {
+            return Reply::status(200).body(content.to_vec());
         };
+        let start: usize = start.trim_end_matches('-').parse().unwrap();
+        Reply::status(206)
+            .header(
+                "Content-Range",
+                &format!("bytes {}-{}/{}", start, content.len() - 1, content.len()),
+            )
+            .body(content[start..].to_vec())
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn cut_connection_resumes_with_range() {
+        let server = TestServer::start(|request| {
+            let range = request.header("Range");
+            match range {
+                None => Reply::status(200).body(content()).cut_after(400),
+                Some(_) => range_reply(&content(), range),
+            }
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let part_path = dir.path().join("file.part");
+
+        assert_eq!(download(&server, &part_path).await.unwrap(), 1000);
+        assert_eq!(std::fs::read(&part_path).unwrap(), content());
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].header("Range"), Some("bytes=400-"));
+    }
+
+    #[tokio::test]
+    async fn partial_content_at_wrong_offset_is_rejected() {
+        let server = TestServer::start(|_| range_reply(&content(), Some("bytes=0-"))).await;
+        let dir = tempfile::tempdir().unwrap();
+        let part_path = dir.path().join("file.part");
+        leave_part(&part_path, "file", &content()[..100]);
+
+        assert!(matches!(
+            download(&server, &part_path).await,
+            Err(ShcError::Deserialize(_))
+        ));
+        assert_eq!(std::fs::read(&part_path).unwrap(), &content()[..100]);
+    }
+
+    #[tokio::test]
+    async fn ignored_range_starts_over() {
+        let server = TestServer::start(|_| Reply::status(200).body(content())).await;
+        let dir = tempfile::tempdir().unwrap();
+        let part_path = dir.path().join("file.part");
+        leave_part(&part_path, "file", &[0xff; 100]);
+
+        assert_eq!(download(&server, &part_path).await.unwrap(), 1000);
+        assert_eq!(std::fs::read(&part_path).unwrap(), content());
+        assert_eq!(server.requests()[0].header("Range"), Some("bytes=100-"));
+    }
+
+    #[tokio::test]
+    async fn range_not_satisfiable_on_complete_part_file_finishes() {
+        let server =
+            TestServer::start(|_| Reply::status(416).header("Content-Range", "bytes */1000")).await;
+        let dir = tempfile::tempdir().unwrap();
+        let part_path = dir.path().join("file.part");
+        leave_part(&part_path, "file", &content());
+
+        assert_eq!(download(&server, &part_path).await.unwrap(), 1000);
+        assert_eq!(std::fs::read(&part_path).unwrap(), content());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn part_of_another_file_is_not_continued() {
+        let server =
+            TestServer::start(|request| range_reply(&content(), request.header("Range"))).await;
+        let dir = tempfile::tempdir().unwrap();
+        let part_path = dir.path().join("file.part");
+        leave_part(&part_path, "other-file", &[0xff; 100]);
+
+        assert_eq!(download(&server, &part_path).await.unwrap(), 1000);
+        assert_eq!(std::fs::read(&part_path).unwrap(), content());
+        assert_eq!(server.requests()[0].header("Range"), None);
+        // the part now belongs to the file it holds
+        let origin_json = std::fs::read(origin_path(&part_path)).unwrap();
+        let found: PartOrigin = serde_json::from_slice(&origin_json).unwrap();
+        assert_eq!(found, origin("file"));
+    }
+
+    #[tokio::test]
+    async fn part_without_origin_is_not_continued() {
+        let server =
+            TestServer::start(|request| range_reply(&content(), request.header("Range"))).await;
+        let dir = tempfile::tempdir().unwrap();
+        let part_path = dir.path().join("file.part");
+        std::fs::write(&part_path, [0xff; 100]).unwrap();
+
+        assert_eq!(download(&server, &part_path).await.unwrap(), 1000);
+        assert_eq!(std::fs::read(&part_path).unwrap(), content());
+        assert_eq!(server.requests()[0].header("Range"), None);
+    }
 }
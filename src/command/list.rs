@@ -1,45 +1,110 @@
 use chrono::{DateTime, Utc};
 use console::style;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
+use std::io::IsTerminal;
+use tokio_stream::StreamExt;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::error::ShcError;
+use crate::models::{ShcFile, ShcFileResponse};
+use crate::output::{self, OutputFormat};
+use crate::tui::{pick_file, shc_file_input, spinner, PickerState};
+use crate::utils::format_bytes;
+
+pub struct ListOptions {
+    pub page: u64,
+    pub limit: u64,
+    // fetch every page instead of only `page`
+    pub all: bool,
+}
 
 pub async fn list_files(
     search: &str,
+    options: &ListOptions,
+    format: OutputFormat,
     api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pb = ProgressBar::new_spinner();
+) -> Result<(), ShcError> {
+    let interactive = format.is_human() && std::io::stdin().is_terminal();
+    let prompt = "Select a file to see more info.";
+
+    if interactive && !options.all {
+        let res = pick_file(search, options.page, options.limit, prompt, api_client).await?;
+        match res {
+            Some(file) => print_file_details(&file, api_client)?,
+            None => println!("No files found."),
+        }
+        return Ok(());
+    }
 
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
+    let pb = spinner("Fetching files...");
 
-    pb.set_message("Fetching files...");
+    let res = if options.all {
+        let mut results = vec![];
+        let files = api_client.list_all_files(search, options.limit);
+        tokio::pin!(files);
+        while let Some(file) = files.next().await {
+            results.push(file?);
+            pb.set_message(format!("Fetching files... ({})", results.len()));
+        }
 
-    let res = api_client.list_files(search).await?;
+        let total_results = results.len() as u64;
+        ShcFileResponse {
+            results,
+            total_results,
+            total_pages: 1,
+            current_page: 1,
+            previous_page: None,
+            next_page: None,
+            per_page: total_results,
+        }
+    } else {
+        api_client
+            .list_files(search, options.page, options.limit)
+            .await?
+    };
     pb.finish_and_clear();
 
+    match format {
+        OutputFormat::Json => return output::print_one(format, &res),
+        OutputFormat::Ndjson | OutputFormat::Csv => {
+            return output::print_many(format, &res.results)
+        }
+        OutputFormat::Table => {}
+    }
+
     if res.results.is_empty() {
         println!("No files found.");
         return Ok(());
     }
 
-    let file_count = res.results.len();
-    let prompt = if file_count > 100 {
-        "Select a file to see more info. (Last 100 files, use filter to get more specific results)"
-            .to_string()
-    } else {
-        format!("Select a file to see more info.  ({} files)", file_count)
-    };
+    // nobody can pick from the list, print it instead
+    if !interactive {
+        let rows: Vec<String> = res
+            .results
+            .iter()
+            .map(|file| {
+                let visibility = if file.is_public { "Public" } else { "Private" };
+                format!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    file.id,
+                    file.name,
+                    format_bytes(file.size),
+                    visibility,
+                    file.updated_at
+                )
+            })
+            .collect();
+        return output::print_lines(&rows);
+    }
 
-    let selection = shc_file_input(&res.results, &prompt);
+    let prompt = format!("{} ({} files)", prompt, res.results.len());
+    let mut state = PickerState::default();
+    let Some(selection) = shc_file_input(&res.results, &prompt, false, false, &mut state)? else {
+        return Ok(());
+    };
+    print_file_details(&res.results[selection[0]], api_client)
+}
 
-    let file = &res.results[selection];
+fn print_file_details(file: &ShcFile, api_client: &api_client::ApiClient) -> Result<(), ShcError> {
     let file_name = &file.name;
     let upload_status = &file.upload_status;
     let updated_at = DateTime::<Utc>::from(DateTime::parse_from_rfc3339(&file.updated_at)?)
@@ -51,7 +116,7 @@ pub async fn list_files(
         format!("{:.3} MB", file.size as f64 / 1024.0 / 1024.0)
     };
     let visibility = if file.is_public { "Public" } else { "Private" };
-    let shareable_link = format!("https://shc-frontend-two.vercel.app/share/{}", file.id);
+    let shareable_link = api_client.share_link(&file.id);
 
     console::Term::stdout()
         .write_line( format!(
@@ -1,7 +1,14 @@
 pub mod add;
 pub mod auth;
+pub mod bulk;
 pub mod get;
 pub mod list;
+pub mod new;
+pub mod profile;
+pub mod prune;
 pub mod remove;
 pub mod rename;
+pub mod sync;
+pub mod ui;
+pub mod verify;
 pub mod visibility;
@@ -0,0 +1,45 @@
+use dialoguer::Editor;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+use crate::api_client;
+use crate::command::add::{upload_file, AddOptions};
+use crate::error::ShcError;
+use crate::output::OutputFormat;
+
+// writes a new text file in $EDITOR and uploads it
+pub async fn new_file(
+    name: &str,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    if !std::io::stdin().is_terminal() {
+        return Err(ShcError::Usage(
+            "`shc new` needs a terminal, use `shc add - --name <NAME>` to upload from stdin"
+                .to_string(),
+        ));
+    }
+
+    // the extension lets the editor pick the right syntax highlighting
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_else(|| ".txt".to_string());
+
+    let text = match Editor::new().extension(&extension).edit("")? {
+        Some(text) if !text.trim().is_empty() => text,
+        // closed without saving or left empty
+        _ => return Err(ShcError::Aborted),
+    };
+
+    let mut file = NamedTempFile::new()?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+
+    let options = AddOptions {
+        name: Some(name.to_string()),
+        ..AddOptions::default()
+    };
+    upload_file(file.path(), &options, format, api_client).await
+}
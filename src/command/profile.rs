@@ -0,0 +1,100 @@
+use console::style;
+
+use crate::error::ShcError;
+use crate::models::{ProfileResult, RemoveProfileResult};
+use crate::output::{self, OutputFormat};
+use crate::user_config::{UserConfig, UserInfo};
+
+fn profile_result(user_config: &UserConfig, name: &str, profile: &UserInfo) -> ProfileResult {
+    ProfileResult {
+        name: name.to_string(),
+        active: name == user_config.profile,
+        email: profile.email.clone(),
+        api_url: profile.api_base_url().to_string(),
+        frontend_url: profile.frontend_url().to_string(),
+    }
+}
+
+pub fn list_profiles(user_config: &UserConfig, format: OutputFormat) -> Result<(), ShcError> {
+    let mut profiles = user_config.profiles.clone();
+    // an unknown --profile is not listed as if it existed
+    if user_config.profile_exists() {
+        profiles.insert(user_config.profile.clone(), user_config.user.clone());
+    }
+
+    if !format.is_human() {
+        let results: Vec<ProfileResult> = profiles
+            .iter()
+            .map(|(name, profile)| profile_result(user_config, name, profile))
+            .collect();
+        return output::print_many(format, &results);
+    }
+
+    for (name, profile) in &profiles {
+        let marker = if *name == user_config.profile {
+            "*"
+        } else {
+            " "
+        };
+        let email = profile.email.as_deref().unwrap_or("not logged in");
+
+        println!(
+            "{} {} ({})\n    API: {}\n    Share links: {}",
+            marker,
+            style(name).cyan(),
+            style(email).yellow(),
+            style(profile.api_base_url()).green(),
+            style(profile.frontend_url()).blue(),
+        );
+    }
+    Ok(())
+}
+
+pub fn set_profile(
+    user_config: &mut UserConfig,
+    api_url: Option<&String>,
+    frontend_url: Option<&String>,
+    format: OutputFormat,
+) -> Result<(), ShcError> {
+    if let Some(api_url) = api_url {
+        if user_config.user.api_base_url.as_ref() != Some(api_url) {
+            // tokens issued by another backend are of no use here
+            user_config.user.access_token = None;
+            user_config.user.refresh_token = None;
+        }
+        user_config.user.api_base_url = Some(api_url.clone());
+    }
+    if let Some(frontend_url) = frontend_url {
+        user_config.user.frontend_url = Some(frontend_url.clone());
+    }
+    user_config.save();
+
+    match format.is_human() {
+        true => println!("Profile \"{}\" saved", user_config.profile),
+        false => output::print_one(
+            format,
+            &profile_result(user_config, &user_config.profile, &user_config.user),
+        )?,
+    }
+    Ok(())
+}
+
+pub fn remove_profile(
+    user_config: &mut UserConfig,
+    name: &str,
+    format: OutputFormat,
+) -> Result<(), ShcError> {
+    let removed = user_config.remove_profile(name);
+    match (format.is_human(), removed) {
+        (true, true) => println!("Profile \"{}\" removed", name),
+        (true, false) => println!("Profile \"{}\" not found", name),
+        (false, _) => output::print_one(
+            format,
+            &RemoveProfileResult {
+                name: name.to_string(),
+                removed,
+            },
+        )?,
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use tokio_stream::StreamExt;
+
+use crate::api_client;
+use crate::command::bulk;
+use crate::consts::DEFAULT_PAGE_SIZE;
+use crate::error::ShcError;
+use crate::file_metadata;
+use crate::models::{PruneResult, ShcFile};
+use crate::output::{self, OutputFormat};
+use crate::tui::spinner;
+
+// removes the files uploaded with --expires-in or --max-downloads that are past their limit
+pub async fn prune_files(
+    dry_run: bool,
+    assume_yes: bool,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    let tracked: HashMap<_, _> = file_metadata::load()
+        .into_iter()
+        .filter(|(_, metadata)| metadata.profile == api_client.profile())
+        .collect();
+    if tracked.is_empty() {
+        match format.is_human() {
+            true => println!("No files with an expiry or download limit."),
+            false => output::print_many::<PruneResult>(format, &[])?,
+        }
+        return Ok(());
+    }
+
+    let pb = spinner("Checking files...");
+
+    // the listing has the download counts, one request per page instead of one per file
+    let mut files = HashMap::new();
+    {
+        let stream = api_client.list_all_files("", DEFAULT_PAGE_SIZE);
+        tokio::pin!(stream);
+        while let Some(file) = stream.next().await {
+            let file = match file {
+                Ok(file) => file,
+                Err(e) => {
+                    pb.finish_and_clear();
+                    return Err(e);
+                }
+            };
+            if tracked.contains_key(&file.id) {
+                files.insert(file.id.clone(), file);
+            }
+        }
+    }
+    pb.finish_and_clear();
+
+    // files removed some other way are not tracked any longer
+    let gone: Vec<String> = tracked
+        .keys()
+        .filter(|file_id| !files.contains_key(*file_id))
+        .cloned()
+        .collect();
+    file_metadata::remove(&gone)?;
+
+    let mut expired: Vec<(ShcFile, &'static str)> = files
+        .into_values()
+        .filter_map(|file| {
+            let reason = tracked[&file.id].expired_reason(file.download_count)?;
+            Some((file, reason))
+        })
+        .collect();
+    expired.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+    if expired.is_empty() {
+        match format.is_human() {
+            true => println!("No expired files."),
+            false => output::print_many::<PruneResult>(format, &[])?,
+        }
+        return Ok(());
+    }
+
+    let reasons: HashMap<String, &'static str> = expired
+        .iter()
+        .map(|(file, reason)| (file.id.clone(), *reason))
+        .collect();
+    let expired: Vec<ShcFile> = expired.into_iter().map(|(file, _)| file).collect();
+
+    if dry_run {
+        let results: Vec<PruneResult> = expired
+            .into_iter()
+            .map(|file| PruneResult {
+                reason: reasons[&file.id].to_string(),
+                file_id: file.id,
+                file_name: file.name,
+            })
+            .collect();
+        if format.is_human() {
+            for result in &results {
+                println!("Would remove \"{}\", {}", result.file_name, result.reason);
+            }
+        } else {
+            output::print_many(format, &results)?;
+        }
+        return Ok(());
+    }
+
+    bulk::confirm_files(
+        &expired,
+        &format!("Remove these {} expired files?", expired.len()),
+        assume_yes,
+    )?;
+
+    let results = bulk::run(
+        expired,
+        "Removing files...",
+        api_client,
+        |file, mut api_client| {
+            let reason = reasons[&file.id].to_string();
+            async move {
+                api_client.remove_file(&file.id).await?;
+                Ok(PruneResult {
+                    file_id: file.id,
+                    file_name: file.name,
+                    reason,
+                })
+            }
+        },
+    )
+    .await?;
+
+    let removed: Vec<String> = results
+        .iter()
+        .filter(|(_, res)| res.is_ok())
+        .map(|(file, _)| file.id.clone())
+        .collect();
+    file_metadata::remove(&removed)?;
+
+    bulk::report(results, format, |result| {
+        format!("Removed \"{}\", {}", result.file_name, result.reason)
+    })
+}
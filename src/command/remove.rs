@@ -1,61 +1,56 @@
-use dialoguer::Confirm;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
-
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::command::bulk::{self, FileFilter};
+use crate::error::ShcError;
+use crate::models::RemoveFileResult;
+use crate::output::{self, OutputFormat};
+use crate::tui::confirm;
 
 pub async fn remove_file(
-    search: &str,
+    target: &str,
+    filter: &FileFilter,
+    assume_yes: bool,
+    format: OutputFormat,
     api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pb = ProgressBar::new_spinner();
-
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
-    pb.set_message("Fetching files...");
-
-    let res = api_client.list_files(search).await?;
-
-    pb.finish_and_clear();
-
-    if res.results.is_empty() {
-        println!("No files found.");
-        return Ok(());
-    };
-
-    let selection = shc_file_input(&res.results, "Which file do you want to remove?");
-
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure?")
-        .default(false)
-        .interact()
-        .unwrap();
-
-    if !confirm {
-        println!("Aborted");
-        return Ok(());
-    } else {
-        let pb = ProgressBar::new_spinner();
-
-        pb.enable_steady_tick(Duration::from_millis(200));
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                .unwrap()
-                .tick_chars("/|\\- "),
-        );
-        pb.set_message("Removing file...");
-        let file_id = res.results[selection].id.clone();
-        let res = api_client.remove_file(file_id.as_str()).await;
-        pb.finish_and_clear();
-        match res {
-            Ok(_) => println!("Done"),
-            Err(e) => println!("Error: {}", e),
+) -> Result<(), ShcError> {
+    let prompt = "Which files do you want to remove?";
+    let files = bulk::select_targets(target, prompt, filter, api_client).await?;
+    match files.as_slice() {
+        [] => {
+            match format.is_human() {
+                true => println!("No files found."),
+                false => output::print_many::<RemoveFileResult>(format, &[])?,
+            }
+            return Ok(());
         }
+        [file] => confirm(
+            &format!("Are you sure you want to remove \"{}\"?", file.name),
+            assume_yes,
+        )?,
+        files => bulk::confirm_files(
+            files,
+            &format!(
+                "Are you sure you want to remove these {} files?",
+                files.len()
+            ),
+            assume_yes,
+        )?,
     }
-    Ok(())
+
+    let results = bulk::run(
+        files,
+        "Removing files...",
+        api_client,
+        |file, mut api_client| async move {
+            api_client.remove_file(&file.id).await?;
+            Ok(RemoveFileResult {
+                file_id: file.id,
+                file_name: file.name,
+            })
+        },
+    )
+    .await?;
+
+    bulk::report(results, format, |result| {
+        format!("Removed \"{}\"", result.file_name)
+    })
 }
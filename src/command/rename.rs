@@ -1,70 +1,67 @@
-use dialoguer::{Confirm, Editor};
-use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
+use dialoguer::Editor;
 
 use crate::api_client;
-use crate::tui::shc_file_input;
+use crate::error::ShcError;
+use crate::models::RenameFileResult;
+use crate::output::{self, OutputFormat};
+use crate::tui::{confirm, ensure_interactive, select_file, spinner};
 
 pub async fn rename_file(
-    search: &str,
+    target: &str,
+    new_name: Option<&str>,
+    assume_yes: bool,
+    format: OutputFormat,
     api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pb = ProgressBar::new_spinner();
-
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
-    pb.set_message("Fetching files...");
-
-    let res = api_client.list_files(search).await?;
-
-    pb.finish_and_clear();
+) -> Result<(), ShcError> {
+    let file = match select_file(target, "Which file do you want to rename?", api_client).await? {
+        Some(file) => file,
+        None => {
+            match format.is_human() {
+                true => println!("No files found."),
+                false => output::print_many::<RenameFileResult>(format, &[])?,
+            }
+            return Ok(());
+        }
+    };
 
-    if res.results.is_empty() {
-        println!("No files found.");
-        return Ok(());
-    }
+    let new_filename = match new_name {
+        Some(new_name) => Some(new_name.to_string()),
+        None => {
+            ensure_interactive("--new-name")?;
+            Editor::new().edit(&file.name)?
+        }
+    };
+    let new_filename = new_filename
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
 
-    let selection = shc_file_input(&res.results, "Which file do you want to rename?");
+    if let Some(new_filename) = new_filename {
+        confirm(
+            &format!("Rename \"{}\" to \"{}\"?", file.name, new_filename),
+            assume_yes,
+        )?;
 
-    if let Some(new_filename) = Editor::new().edit("new filename").unwrap() {
-        let confirm = Confirm::new()
-            .with_prompt("Are you sure?")
-            .default(false)
-            .interact()
-            .unwrap();
+        let pb = spinner("Renaming file...");
+        let res = api_client
+            .rename_file(file.id.as_str(), new_filename.as_str())
+            .await;
+        pb.finish_and_clear();
+        res?;
 
-        if !confirm {
-            println!("Aborted");
-            return Ok(());
+        if format.is_human() {
+            println!("File renamed successfully");
         } else {
-            let file_id = res.results[selection].id.clone();
-            let pb = ProgressBar::new_spinner();
-
-            pb.enable_steady_tick(Duration::from_millis(200));
-            pb.set_style(
-                ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                    .unwrap()
-                    .tick_chars("/|\\- "),
-            );
-            pb.set_message("Renaming file...");
-            let res = api_client.rename_file(file_id.as_str(), new_filename.as_str());
-            pb.finish_and_clear();
-            match res.await {
-                Ok(_) => {
-                    println!("File renamed successfully");
-                }
-                Err(e) => {
-                    println!("Error: {}", e);
-                }
-            }
+            output::print_one(
+                format,
+                &RenameFileResult {
+                    file_id: file.id,
+                    old_name: file.name,
+                    new_name: new_filename,
+                },
+            )?;
         }
     } else {
-        // TODO: Handle empty filename correctly
-        println!("ShcFile name cannot be empty");
+        return Err(ShcError::Usage("ShcFile name cannot be empty".to_string()));
     }
 
     Ok(())
@@ -0,0 +1,333 @@
+use console::style;
+use reqwest::StatusCode;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::api_client;
+use crate::checksum::sha256_file;
+use crate::command::add::{self, AddOptions};
+use crate::error::ShcError;
+use crate::interrupt::CleanupGuard;
+use crate::models::SyncResult;
+use crate::output::{self, OutputFormat};
+use crate::sync_manifest::{SyncManifest, SyncedFile};
+use crate::tui::{confirm, spinner};
+use crate::upload_journal::file_stamp;
+use crate::utils::folder_walker;
+
+pub struct SyncOptions {
+    // remove uploaded files that are not in the folder any more
+    pub delete: bool,
+    // only print what would be done
+    pub dry_run: bool,
+}
+
+// what has to happen to a file of the folder, with the id of its uploaded copy
+enum Change {
+    Add,
+    Update(String),
+    Remove(String),
+    // not in the folder any more, kept on the server without --delete
+    Orphan(String),
+}
+
+impl Change {
+    fn action(&self) -> &'static str {
+        match self {
+            Change::Add => "add",
+            Change::Update(_) => "update",
+            Change::Remove(_) => "remove",
+            Change::Orphan(_) => "orphan",
+        }
+    }
+
+    fn file_id(&self) -> Option<String> {
+        match self {
+            Change::Add => None,
+            Change::Update(file_id) | Change::Remove(file_id) | Change::Orphan(file_id) => {
+                Some(file_id.clone())
+            }
+        }
+    }
+}
+
+// uploads the new and changed files of a folder, every file on its own and named like
+// `dist/assets/app.js`, changed files keep their share link
+pub async fn sync_dir(
+    dir: &Path,
+    options: &SyncOptions,
+    assume_yes: bool,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    if !dir.is_dir() {
+        return Err(ShcError::Usage(format!(
+            "{} is not a folder",
+            dir.display()
+        )));
+    }
+    let dir = std::fs::canonicalize(dir)?;
+    let dir_name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let profile = api_client.profile().to_string();
+    let mut manifest = SyncManifest::new();
+    let mut synced = manifest.get(&dir, &profile);
+
+    let pb = spinner("Comparing files...");
+
+    let mut changes = vec![];
+    let mut local = HashSet::new();
+    // the same files a folder upload would zip
+    let paths = folder_walker(&dir)
+        .filter_map(Result::ok)
+        .map(ignore::DirEntry::into_path)
+        .filter(|path| path.is_file());
+    for path in paths {
+        let Some((size, modified)) = file_stamp(&path) else {
+            continue;
+        };
+        let relative_path = relative_path(&dir, &path);
+        local.insert(relative_path.clone());
+
+        match synced.get_mut(&relative_path) {
+            Some(file) if file.size == size && file.modified == modified => {}
+            Some(file) => {
+                // rewritten with the same content, e.g. by a build that did not change anything
+                if sha256_file(&path)? == file.sha256 {
+                    file.modified = modified;
+                } else {
+                    changes.push((relative_path, Change::Update(file.file_id.clone())));
+                }
+            }
+            None => changes.push((relative_path, Change::Add)),
+        }
+    }
+    for (relative_path, file) in &synced {
+        if !local.contains(relative_path) {
+            let file_id = file.file_id.clone();
+            let change = match options.delete {
+                true => Change::Remove(file_id),
+                false => Change::Orphan(file_id),
+            };
+            changes.push((relative_path.clone(), change));
+        }
+    }
+    changes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    pb.finish_and_clear();
+
+    if options.dry_run {
+        return print_plan(&changes, format);
+    }
+    manifest.set(&dir, &profile, synced.clone())?;
+
+    let orphans = changes
+        .iter()
+        .filter(|(_, change)| matches!(change, Change::Orphan(_)))
+        .count();
+    if changes.len() == orphans {
+        match format.is_human() {
+            true => println!("Everything is up to date."),
+            false => output::print_many::<SyncResult>(format, &[])?,
+        }
+        print_orphans_hint(orphans, format);
+        return Ok(());
+    }
+
+    let removed: Vec<&String> = changes
+        .iter()
+        .filter(|(_, change)| matches!(change, Change::Remove(_)))
+        .map(|(relative_path, _)| relative_path)
+        .collect();
+    if !removed.is_empty() {
+        for relative_path in &removed {
+            eprintln!("  {}", relative_path);
+        }
+        confirm(
+            &format!(
+                "Remove these {} files that are not in {} any more?",
+                removed.len(),
+                dir_name
+            ),
+            assume_yes,
+        )?;
+    }
+
+    let mut rows = vec![];
+    let mut first_error = None;
+    for (relative_path, change) in changes {
+        let path = dir.join(&relative_path);
+        let res = match &change {
+            Change::Add | Change::Update(_) => {
+                // taken before the upload, a change while uploading is picked up next time
+                let stamp = file_stamp(&path);
+                let add_options = AddOptions {
+                    name: Some(format!("{}/{}", dir_name, relative_path)),
+                    replace: change.file_id(),
+                    ..AddOptions::default()
+                };
+                let _cleanup = CleanupGuard::new();
+                let res = match add::upload_path(&path, &add_options, api_client).await {
+                    // removed on the server in the meantime, upload it as a new file
+                    Err(ShcError::Http { status, .. }) if status == StatusCode::NOT_FOUND => {
+                        let add_options = AddOptions {
+                            replace: None,
+                            ..add_options
+                        };
+                        add::upload_path(&path, &add_options, api_client).await
+                    }
+                    res => res,
+                };
+                res.map(|result| {
+                    let (size, modified) = stamp.unwrap_or_default();
+                    let file = SyncedFile {
+                        file_id: result.file_id.clone(),
+                        size,
+                        modified,
+                        sha256: result.sha256,
+                    };
+                    synced.insert(relative_path.clone(), file);
+                    (Some(result.file_id), Some(result.share_link))
+                })
+            }
+            Change::Remove(file_id) => match api_client.remove_file(file_id).await {
+                Ok(()) => Ok((Some(file_id.clone()), None)),
+                Err(ShcError::Http { status, .. }) if status == StatusCode::NOT_FOUND => {
+                    Ok((Some(file_id.clone()), None))
+                }
+                Err(e) => Err(e),
+            }
+            .inspect(|_| {
+                synced.remove(&relative_path);
+            }),
+            Change::Orphan(_) => continue,
+        };
+        // saved after every file, so an interrupted sync does not upload the finished ones again
+        manifest.set(&dir, &profile, synced.clone())?;
+
+        let row = match res {
+            Ok((file_id, share_link)) => SyncResult {
+                path: relative_path,
+                action: change.action().to_string(),
+                file_id,
+                share_link,
+                error: None,
+            },
+            Err(ShcError::Interrupted) => return Err(ShcError::Interrupted),
+            Err(e) => {
+                let row = SyncResult {
+                    path: relative_path,
+                    action: change.action().to_string(),
+                    file_id: change.file_id(),
+                    share_link: None,
+                    error: Some(e.to_string()),
+                };
+                first_error.get_or_insert(e);
+                row
+            }
+        };
+        rows.push(row);
+    }
+
+    if format.is_human() {
+        print_summary(&rows);
+    } else {
+        output::print_many(format, &rows)?;
+    }
+    print_orphans_hint(orphans, format);
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn print_plan(changes: &[(String, Change)], format: OutputFormat) -> Result<(), ShcError> {
+    if !format.is_human() {
+        let rows: Vec<SyncResult> = changes
+            .iter()
+            .map(|(relative_path, change)| SyncResult {
+                path: relative_path.clone(),
+                action: change.action().to_string(),
+                file_id: change.file_id(),
+                share_link: None,
+                error: None,
+            })
+            .collect();
+        return output::print_many(format, &rows);
+    }
+
+    if changes.is_empty() {
+        println!("Everything is up to date.");
+    }
+    for (relative_path, change) in changes {
+        match change {
+            Change::Add => println!("{} {}", style("+").green(), relative_path),
+            Change::Update(_) => println!("{} {}", style("~").yellow(), relative_path),
+            Change::Remove(_) => println!("{} {}", style("-").red(), relative_path),
+            Change::Orphan(_) => println!(
+                "{} {} {}",
+                style("?").dim(),
+                relative_path,
+                style("not in the folder any more, --delete removes it").dim()
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn print_summary(rows: &[SyncResult]) {
+    let path_width = rows.iter().map(|row| row.path.len()).max().unwrap_or(0);
+    let count = |action: &str| {
+        rows.iter()
+            .filter(|row| row.action == action && row.error.is_none())
+            .count()
+    };
+
+    for row in rows {
+        match &row.error {
+            None => println!(
+                "{} {:<7} {:<path_width$}  {}",
+                style("✔").green(),
+                row.action,
+                row.path,
+                row.share_link.as_deref().unwrap_or_default()
+            ),
+            Some(error) => println!(
+                "{} {:<7} {:<path_width$}  {}",
+                style("✘").red(),
+                row.action,
+                row.path,
+                style(error).red()
+            ),
+        }
+    }
+    let failed = rows.iter().filter(|row| row.error.is_some()).count();
+    println!(
+        "\n{} added, {} updated, {} removed, {} failed",
+        count("add"),
+        count("update"),
+        count("remove"),
+        failed
+    );
+}
+
+fn print_orphans_hint(orphans: usize, format: OutputFormat) {
+    if orphans > 0 && format.is_human() {
+        println!(
+            "{} uploaded files are not in the folder any more, `--delete` removes them",
+            orphans
+        );
+    }
+}
+
+// path inside the synced folder with / as separator, the same on every platform
+fn relative_path(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,631 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap};
+use std::cmp::Ordering;
+use std::io::{stdout, Stdout, Write};
+use tokio_stream::StreamExt;
+
+use crate::api_client::ApiClient;
+use crate::command::bulk::FileFilter;
+use crate::command::get::{download_file, GetOptions, OnConflict};
+use crate::consts::DEFAULT_PAGE_SIZE;
+use crate::error::ShcError;
+use crate::models::ShcFile;
+use crate::output::OutputFormat;
+use crate::tui::{ensure_interactive, spinner};
+use crate::utils::format_bytes;
+
+const HELP: &str =
+    "↑↓ move  / filter  s sort  S reverse  d download  r rename  v visibility  c copy link  x remove  R reload  q quit";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Name,
+    Size,
+    Updated,
+    Visibility,
+}
+
+impl SortBy {
+    fn next(self) -> SortBy {
+        match self {
+            SortBy::Name => SortBy::Size,
+            SortBy::Size => SortBy::Updated,
+            SortBy::Updated => SortBy::Visibility,
+            SortBy::Visibility => SortBy::Name,
+        }
+    }
+
+    fn compare(self, a: &ShcFile, b: &ShcFile) -> Ordering {
+        match self {
+            SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortBy::Size => a.size.cmp(&b.size),
+            // the server sends UTC timestamps, they sort like strings
+            SortBy::Updated => a.updated_at.cmp(&b.updated_at),
+            SortBy::Visibility => a.is_public.cmp(&b.is_public),
+        }
+    }
+}
+
+enum Mode {
+    Browse,
+    // typing into the filter, the table narrows with every key
+    Filter,
+    Rename(String),
+    ConfirmRemove,
+}
+
+struct App {
+    files: Vec<ShcFile>,
+    // indices into `files` that match the filter, in sort order
+    rows: Vec<usize>,
+    filter: String,
+    sort_by: SortBy,
+    descending: bool,
+    table: TableState,
+    mode: Mode,
+    // result of the last action, shown instead of the help until the next key
+    status: Option<String>,
+}
+
+impl App {
+    fn new(files: Vec<ShcFile>) -> Self {
+        let mut app = App {
+            files,
+            rows: vec![],
+            filter: String::new(),
+            sort_by: SortBy::Updated,
+            descending: true,
+            table: TableState::default(),
+            mode: Mode::Browse,
+            status: None,
+        };
+        app.update_rows();
+        app
+    }
+
+    // re-applies filter and sort, keeping the selected file selected if it is still shown
+    fn update_rows(&mut self) {
+        let selected_id = self.selected().map(|file| file.id.clone());
+        let filter = self.filter.to_lowercase();
+
+        self.rows = (0..self.files.len())
+            .filter(|&index| self.files[index].name.to_lowercase().contains(&filter))
+            .collect();
+        let (files, sort_by) = (&self.files, self.sort_by);
+        self.rows
+            .sort_by(|&a, &b| sort_by.compare(&files[a], &files[b]));
+        if self.descending {
+            self.rows.reverse();
+        }
+
+        let selected = selected_id
+            .and_then(|id| {
+                self.rows
+                    .iter()
+                    .position(|&index| self.files[index].id == id)
+            })
+            .unwrap_or(0);
+        self.table
+            .select((!self.rows.is_empty()).then_some(selected));
+    }
+
+    fn selected(&self) -> Option<&ShcFile> {
+        let row = self.table.selected()?;
+        self.rows.get(row).map(|&index| &self.files[index])
+    }
+
+    fn selected_mut(&mut self) -> Option<&mut ShcFile> {
+        let row = self.table.selected()?;
+        self.rows.get(row).map(|&index| &mut self.files[index])
+    }
+
+    fn move_selection(&mut self, by: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let last = self.rows.len() as isize - 1;
+        self.table
+            .select(Some((current + by).clamp(0, last) as usize));
+    }
+}
+
+// switches the terminal to a full screen that takes every key, and back when dropped
+struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Screen {
+    fn enter() -> Result<Self, ShcError> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(stdout(), EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+        terminal.clear()?;
+        Ok(Screen { terminal })
+    }
+
+    // hands the normal terminal back for a while, e.g. to show a download's progress bar
+    fn suspend(&mut self) -> Result<(), ShcError> {
+        crossterm::execute!(stdout(), LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+        self.terminal.show_cursor()?;
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), ShcError> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(stdout(), EnterAlternateScreen)?;
+        self.terminal.clear()?;
+        Ok(())
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(stdout(), LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+// full screen file browser, run by `shc ui` or `shc` without a subcommand
+pub async fn browse(api_client: &mut ApiClient) -> Result<(), ShcError> {
+    ensure_interactive("a subcommand")?;
+
+    let pb = spinner("Fetching files...");
+    let files = fetch_files(api_client).await;
+    pb.finish_and_clear();
+
+    let mut app = App::new(files?);
+    // a login prompt can't be shown on the full screen, an expired session shows as an error
+    api_client.set_login_again(false);
+    let mut screen = Screen::enter()?;
+
+    loop {
+        screen
+            .terminal
+            .draw(|frame| draw(frame, &mut app, api_client))?;
+
+        let Event::Key(key) = next_event().await? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        app.status = None;
+
+        let mode = std::mem::replace(&mut app.mode, Mode::Browse);
+        match mode {
+            Mode::Browse => {
+                if !handle_browse_key(key, &mut app, &mut screen, api_client).await? {
+                    return Ok(());
+                }
+            }
+            Mode::Filter => match key.code {
+                KeyCode::Enter => {}
+                KeyCode::Esc => {
+                    app.filter.clear();
+                    app.update_rows();
+                }
+                KeyCode::Backspace => {
+                    app.filter.pop();
+                    app.update_rows();
+                    app.mode = Mode::Filter;
+                }
+                KeyCode::Char(c) => {
+                    app.filter.push(c);
+                    app.update_rows();
+                    app.mode = Mode::Filter;
+                }
+                _ => app.mode = Mode::Filter,
+            },
+            Mode::Rename(mut new_name) => match key.code {
+                KeyCode::Enter => {
+                    let new_name = new_name.trim().to_string();
+                    if !new_name.is_empty() {
+                        rename(&new_name, &mut app, &mut screen, api_client).await?;
+                    }
+                }
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    new_name.pop();
+                    app.mode = Mode::Rename(new_name);
+                }
+                KeyCode::Char(c) => {
+                    new_name.push(c);
+                    app.mode = Mode::Rename(new_name);
+                }
+                _ => app.mode = Mode::Rename(new_name),
+            },
+            Mode::ConfirmRemove => {
+                if let KeyCode::Char('y') = key.code {
+                    remove(&mut app, &mut screen, api_client).await?;
+                }
+            }
+        }
+    }
+}
+
+// returns false once the user quits
+async fn handle_browse_key(
+    key: KeyEvent,
+    app: &mut App,
+    screen: &mut Screen,
+    api_client: &mut ApiClient,
+) -> Result<bool, ShcError> {
+    // raw mode turns Ctrl-C into a key instead of a signal
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Ok(false);
+    }
+
+    match key.code {
+        KeyCode::Char('q') => return Ok(false),
+        KeyCode::Esc if app.filter.is_empty() => return Ok(false),
+        KeyCode::Esc => {
+            app.filter.clear();
+            app.update_rows();
+        }
+        KeyCode::Up | KeyCode::Char('k') => app.move_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.move_selection(1),
+        KeyCode::PageUp => app.move_selection(-10),
+        KeyCode::PageDown => app.move_selection(10),
+        KeyCode::Home | KeyCode::Char('g') => app.move_selection(isize::MIN / 2),
+        KeyCode::End | KeyCode::Char('G') => app.move_selection(isize::MAX / 2),
+        KeyCode::Char('/') => app.mode = Mode::Filter,
+        KeyCode::Char('s') => {
+            app.sort_by = app.sort_by.next();
+            app.update_rows();
+        }
+        KeyCode::Char('S') => {
+            app.descending = !app.descending;
+            app.update_rows();
+        }
+        KeyCode::Char('R') => {
+            app.status = Some("Fetching files...".to_string());
+            screen.terminal.draw(|frame| draw(frame, app, api_client))?;
+            match fetch_files(api_client).await {
+                Ok(files) => {
+                    app.files = files;
+                    app.update_rows();
+                    app.status = Some(format!("{} files", app.files.len()));
+                }
+                Err(e) => app.status = Some(format!("error: {}", e)),
+            }
+        }
+        KeyCode::Char('r') => {
+            if let Some(file) = app.selected() {
+                app.mode = Mode::Rename(file.name.clone());
+            }
+        }
+        KeyCode::Char('x') | KeyCode::Delete if app.selected().is_some() => {
+            app.mode = Mode::ConfirmRemove;
+        }
+        KeyCode::Char('v') => toggle_visibility(app, screen, api_client).await?,
+        KeyCode::Char('c') | KeyCode::Char('y') => {
+            if let Some(file) = app.selected() {
+                let share_link = api_client.share_link(&file.id);
+                copy_to_clipboard(&share_link)?;
+                app.status = Some(format!("Copied {}", share_link));
+            }
+        }
+        KeyCode::Char('d') | KeyCode::Enter => download(app, screen, api_client).await?,
+        _ => {}
+    }
+    Ok(true)
+}
+
+async fn next_event() -> Result<Event, ShcError> {
+    // reading blocks until a key is pressed, keep it off the async workers
+    let event = tokio::task::spawn_blocking(event::read)
+        .await
+        .map_err(std::io::Error::other)??;
+    Ok(event)
+}
+
+async fn fetch_files(api_client: &mut ApiClient) -> Result<Vec<ShcFile>, ShcError> {
+    let mut files = vec![];
+    let stream = api_client.list_all_files("", DEFAULT_PAGE_SIZE);
+    tokio::pin!(stream);
+    while let Some(file) = stream.next().await {
+        files.push(file?);
+    }
+    Ok(files)
+}
+
+// shows what is about to happen, the api calls block the loop until they are done
+fn show_busy(
+    message: String,
+    app: &mut App,
+    screen: &mut Screen,
+    api_client: &ApiClient,
+) -> Result<(), ShcError> {
+    app.status = Some(message);
+    screen.terminal.draw(|frame| draw(frame, app, api_client))?;
+    Ok(())
+}
+
+async fn rename(
+    new_name: &str,
+    app: &mut App,
+    screen: &mut Screen,
+    api_client: &mut ApiClient,
+) -> Result<(), ShcError> {
+    let Some(file) = app.selected() else {
+        return Ok(());
+    };
+    let file_id = file.id.clone();
+
+    show_busy("Renaming file...".to_string(), app, screen, api_client)?;
+    app.status = Some(match api_client.rename_file(&file_id, new_name).await {
+        Ok(()) => {
+            if let Some(file) = app.selected_mut() {
+                file.name = new_name.to_string();
+            }
+            app.update_rows();
+            format!("Renamed to {}", new_name)
+        }
+        Err(e) => format!("error: {}", e),
+    });
+    Ok(())
+}
+
+async fn remove(
+    app: &mut App,
+    screen: &mut Screen,
+    api_client: &mut ApiClient,
+) -> Result<(), ShcError> {
+    let Some(file) = app.selected() else {
+        return Ok(());
+    };
+    let (file_id, file_name) = (file.id.clone(), file.name.clone());
+
+    show_busy(
+        format!("Removing {}...", file_name),
+        app,
+        screen,
+        api_client,
+    )?;
+    app.status = Some(match api_client.remove_file(&file_id).await {
+        Ok(()) => {
+            app.files.retain(|file| file.id != file_id);
+            let selected = app.table.selected();
+            app.update_rows();
+            // stay at the same row instead of jumping to the top
+            if let Some(selected) = selected {
+                app.table.select(Some(selected));
+                app.move_selection(0);
+            }
+            format!("Removed {}", file_name)
+        }
+        Err(e) => format!("error: {}", e),
+    });
+    Ok(())
+}
+
+async fn toggle_visibility(
+    app: &mut App,
+    screen: &mut Screen,
+    api_client: &mut ApiClient,
+) -> Result<(), ShcError> {
+    let Some(file) = app.selected() else {
+        return Ok(());
+    };
+    let file_id = file.id.clone();
+
+    show_busy(
+        "Toggling visibility...".to_string(),
+        app,
+        screen,
+        api_client,
+    )?;
+    app.status = Some(match api_client.toggle_file_visibility(&file_id).await {
+        Ok(res) => {
+            if let Some(file) = app.selected_mut() {
+                file.is_public = res.is_public;
+            }
+            app.update_rows();
+            let visibility = if res.is_public { "Public" } else { "Private" };
+            format!("{} is now {}", res.name, visibility)
+        }
+        Err(e) => format!("error: {}", e),
+    });
+    Ok(())
+}
+
+// downloads to the current directory like `shc get`, on the normal screen so its progress shows
+async fn download(
+    app: &mut App,
+    screen: &mut Screen,
+    api_client: &mut ApiClient,
+) -> Result<(), ShcError> {
+    let Some(file) = app.selected() else {
+        return Ok(());
+    };
+    let file_id = file.id.clone();
+    let options = GetOptions {
+        out: None,
+        on_conflict: OnConflict::Rename,
+    };
+
+    screen.suspend()?;
+    let res = download_file(
+        &file_id,
+        &FileFilter::default(),
+        true,
+        &options,
+        OutputFormat::Table,
+        api_client,
+    )
+    .await;
+    screen.resume()?;
+
+    app.status = Some(match res {
+        Ok(()) => "Downloaded to the current directory".to_string(),
+        // Ctrl-C ends the whole program, not just the download
+        Err(ShcError::Interrupted) => return Err(ShcError::Interrupted),
+        Err(e) => format!("error: {}", e),
+    });
+    Ok(())
+}
+
+// OSC 52 asks the terminal itself to set the clipboard, which also works over ssh
+fn copy_to_clipboard(text: &str) -> Result<(), ShcError> {
+    let mut out = stdout();
+    write!(out, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    out.flush()?;
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, app: &mut App, api_client: &ApiClient) {
+    let [title, main, footer] = *Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .split(frame.size()) else {
+        return;
+    };
+    let [table, details] =
+        *Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).split(main)
+    else {
+        return;
+    };
+
+    let title_line = match (&app.mode, app.filter.is_empty()) {
+        (Mode::Filter, _) => Line::from(vec![
+            Span::raw("Filter: ").bold(),
+            Span::raw(&app.filter),
+            Span::raw("█"),
+        ]),
+        (_, false) => Line::from(vec![
+            Span::raw(format!(
+                "{} of {} files matching ",
+                app.rows.len(),
+                app.files.len()
+            )),
+            Span::raw(&app.filter).cyan(),
+        ]),
+        (_, true) => Line::from(format!("shc - {} files", app.files.len())),
+    };
+    frame.render_widget(Paragraph::new(title_line), title);
+
+    draw_table(frame, table, app);
+    draw_details(frame, details, app, api_client);
+
+    let footer_line = match &app.mode {
+        Mode::Rename(new_name) => Line::from(vec![
+            Span::raw("New name: ").bold(),
+            Span::raw(new_name.as_str()),
+            Span::raw("█"),
+        ]),
+        Mode::ConfirmRemove => Line::from(
+            format!(
+                "Remove \"{}\"? y/N",
+                app.selected()
+                    .map(|file| file.name.as_str())
+                    .unwrap_or_default()
+            )
+            .red()
+            .bold(),
+        ),
+        _ => match &app.status {
+            Some(status) => Line::from(status.as_str().yellow()),
+            None => Line::from(HELP.dim()),
+        },
+    };
+    frame.render_widget(Paragraph::new(footer_line), footer);
+}
+
+fn draw_table(frame: &mut Frame, area: Rect, app: &mut App) {
+    let arrow = if app.descending { " ▼" } else { " ▲" };
+    let header = [
+        ("Name", SortBy::Name),
+        ("Size", SortBy::Size),
+        ("Updated", SortBy::Updated),
+        ("Visibility", SortBy::Visibility),
+    ]
+    .map(|(title, sort_by)| match sort_by == app.sort_by {
+        true => Cell::from(format!("{}{}", title, arrow)),
+        false => Cell::from(title),
+    });
+
+    let date_formatter = timeago::Formatter::new();
+    let rows = app.rows.iter().map(|&index| {
+        let file = &app.files[index];
+        let updated = DateTime::parse_from_rfc3339(&file.updated_at)
+            .map(|updated_at| date_formatter.convert_chrono(updated_at, Utc::now()))
+            .unwrap_or_else(|_| file.updated_at.clone());
+        let visibility = if file.is_public { "Public" } else { "Private" };
+        Row::new([
+            Cell::from(file.name.as_str()),
+            Cell::from(format_bytes(file.size)),
+            Cell::from(updated),
+            Cell::from(visibility),
+        ])
+    });
+
+    // long names are cut at the column instead of pushing the other columns out
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(10),
+        Constraint::Length(16),
+        Constraint::Length(12),
+    ];
+    let table = Table::new(rows, widths)
+        .header(Row::new(header).bold().underlined())
+        .highlight_style(Style::new().reversed())
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_stateful_widget(table, area, &mut app.table);
+}
+
+fn draw_details(frame: &mut Frame, area: Rect, app: &App, api_client: &ApiClient) {
+    let block = Block::default().borders(Borders::ALL).title("Details");
+    let Some(file) = app.selected() else {
+        frame.render_widget(Paragraph::new("No files found.").block(block), area);
+        return;
+    };
+
+    let updated_at = DateTime::parse_from_rfc3339(&file.updated_at)
+        .map(|updated_at| {
+            DateTime::<Utc>::from(updated_at)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|_| file.updated_at.clone());
+    let visibility = if file.is_public { "Public" } else { "Private" };
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![
+            Span::raw(format!("{:<11}", name)).dim(),
+            Span::raw(value),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(file.name.as_str().cyan().bold()),
+        Line::default(),
+        field("Id", file.id.clone()),
+        field("Type", file.mime_type.clone()),
+        field("Size", format_bytes(file.size)),
+        field("Visibility", visibility.to_string()),
+        field("Status", file.upload_status.clone()),
+        field("Updated", updated_at),
+    ];
+    if let Some(sha256) = &file.sha256 {
+        lines.push(field("SHA-256", sha256.clone()));
+    }
+    lines.push(Line::default());
+    lines.push(Line::from("Share link".dim()));
+    lines.push(Line::from(
+        api_client.share_link(&file.id).blue().underlined(),
+    ));
+
+    let details = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(details, area);
+}
@@ -0,0 +1,85 @@
+use console::style;
+use std::path::Path;
+
+use crate::api_client;
+use crate::checksum::{check_sha256, sha256_file_async};
+use crate::error::ShcError;
+use crate::models::VerifyResult;
+use crate::output::{self, OutputFormat};
+use crate::tui::{select_file, spinner};
+
+// compares the SHA-256 of a local file with the one recorded when the remote file was uploaded
+pub async fn verify_file(
+    target: &str,
+    local_path: &Path,
+    format: OutputFormat,
+    api_client: &mut api_client::ApiClient,
+) -> Result<(), ShcError> {
+    let prompt = "Which file do you want to compare with?";
+    let file = match select_file(target, prompt, api_client).await? {
+        Some(file) => file,
+        // nothing to compare with is a failed check, not a passed one
+        None => {
+            return Err(ShcError::Usage(format!(
+                "no file matches \"{}\", nothing to compare with",
+                target
+            )))
+        }
+    };
+
+    // the message is set by each step below
+    let pb = spinner("");
+
+    // files from the picker may come without their checksum
+    let file = match file.sha256 {
+        Some(_) => file,
+        None => {
+            pb.set_message("Fetching file...");
+            let res = api_client.get_file_download_url(&file.id).await;
+            if res.is_err() {
+                pb.finish_and_clear();
+            }
+            res?
+        }
+    };
+    let expected = match &file.sha256 {
+        Some(expected) => expected.clone(),
+        None => {
+            pb.finish_and_clear();
+            return Err(ShcError::Deserialize(format!(
+                "server has no checksum for \"{}\", it was uploaded before checksums were recorded",
+                file.name
+            )));
+        }
+    };
+
+    pb.set_message("Computing checksum...");
+    let actual = sha256_file_async(local_path).await;
+    pb.finish_and_clear();
+    let actual = actual?;
+
+    let matches = actual.eq_ignore_ascii_case(&expected);
+    if format.is_human() {
+        if matches {
+            println!(
+                "{} {} matches \"{}\"",
+                style("✔").green(),
+                local_path.display(),
+                file.name
+            );
+        }
+    } else {
+        output::print_one(
+            format,
+            &VerifyResult {
+                file_id: file.id,
+                file_name: file.name,
+                path: local_path.to_string_lossy().to_string(),
+                sha256: actual.clone(),
+                matches,
+            },
+        )?;
+    }
+
+    check_sha256(&expected, actual)
+}
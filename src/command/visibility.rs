@@ -1,63 +1,81 @@
-use dialoguer::Confirm;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
+use crate::api_client;
+use crate::command::bulk::{self, FileFilter};
+use crate::error::ShcError;
+use crate::models::VisibilityResult;
+use crate::output::{self, OutputFormat};
+use crate::tui::confirm;
 
-use crate::{api_client, tui::shc_file_input};
-
-pub async fn toggle_file_visibility(
-    search: &str,
+// toggles the visibility, or sets it when `public` is given so running it twice changes nothing
+pub async fn change_file_visibility(
+    target: &str,
+    filter: &FileFilter,
+    public: Option<bool>,
+    assume_yes: bool,
+    format: OutputFormat,
     api_client: &mut api_client::ApiClient,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pb = ProgressBar::new_spinner();
-
-    pb.enable_steady_tick(Duration::from_millis(200));
-    pb.set_style(
-        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-            .unwrap()
-            .tick_chars("/|\\- "),
-    );
-    pb.set_message("Fetching files...");
-    let res = api_client.list_files(search).await?;
-    pb.finish_and_clear();
-
-    if res.results.is_empty() {
-        println!("No files found.");
-        return Ok(());
+) -> Result<(), ShcError> {
+    let prompt = "Which files do you want to change visibility?";
+    let files = bulk::select_targets(target, prompt, filter, api_client).await?;
+    match files.as_slice() {
+        [] => {
+            match format.is_human() {
+                true => println!("No files found."),
+                false => output::print_many::<VisibilityResult>(format, &[])?,
+            }
+            return Ok(());
+        }
+        [file] => {
+            let new_visibility = match public.unwrap_or(!file.is_public) {
+                true => "Public",
+                false => "Private",
+            };
+            confirm(
+                &format!("Make \"{}\" {}?", file.name, new_visibility),
+                assume_yes,
+            )?
+        }
+        files => {
+            let prompt = match public {
+                Some(true) => format!("Make these {} files Public?", files.len()),
+                Some(false) => format!("Make these {} files Private?", files.len()),
+                None => format!("Toggle the visibility of these {} files?", files.len()),
+            };
+            bulk::confirm_files(files, &prompt, assume_yes)?
+        }
     }
 
-    let selection = shc_file_input(&res.results, "Which file do you want to change visibility?");
-
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure?")
-        .default(false)
-        .interact()
-        .unwrap();
-
-    if !confirm {
-        println!("Aborted");
-        return Ok(());
-    } else {
-        let pb = ProgressBar::new_spinner();
+    let message = "Changing visibility...";
+    let results = bulk::run(
+        files,
+        message,
+        api_client,
+        move |file, mut api_client| async move {
+            let (res, changed) = match public {
+                Some(public) => api_client.set_file_visibility(&file.id, public).await?,
+                None => (api_client.toggle_file_visibility(&file.id).await?, true),
+            };
+            Ok(VisibilityResult {
+                file_id: res.id,
+                file_name: res.name,
+                is_public: res.is_public,
+                changed,
+            })
+        },
+    )
+    .await?;
 
-        pb.enable_steady_tick(Duration::from_millis(200));
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
-                .unwrap()
-                .tick_chars("/|\\- "),
-        );
-        pb.set_message("Toggling visibility...");
-        let file_id = res.results[selection].id.clone();
-        let res = api_client.toggle_file_visibility(file_id.as_str()).await;
-        pb.finish_and_clear();
-        match res {
-            Ok(res) => {
-                let visiblity = if res.is_public { "Public" } else { "Private" };
-                println!("Visibility of \"{}\" changed to {}", res.name, visiblity);
-            }
-            Err(e) => {
-                println!("Error: {}", e)
-            }
+    bulk::report(results, format, |result| {
+        let visiblity = if result.is_public {
+            "Public"
+        } else {
+            "Private"
+        };
+        match result.changed {
+            true => format!(
+                "Visibility of \"{}\" changed to {}",
+                result.file_name, visiblity
+            ),
+            false => format!("\"{}\" is already {}", result.file_name, visiblity),
         }
-    }
-    Ok(())
+    })
 }
@@ -1,12 +1,45 @@
 pub const SHC_BACKEND_API_BASE_URL: &str = "https://shc-backend-production.up.railway.app";
+pub const SHC_FRONTEND_BASE_URL: &str = "https://shc-frontend-two.vercel.app";
 
 // what is the meaning of &str? and what is the meaning of pub?
 // &str -> It’s like pointing to a piece of text without owning it, meaning you can use it, but you can’t modify the original string. ✅
 // pub -> It’s like saying “Hey, this thing is public, so you can use it from outside this module!” ✅
 pub const SHC_CLI_FOLDER_NAME: &str = ".shc-cli";
 pub const USER_CONFIG_FILE_NAME: &str = "config.toml";
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+pub const UPLOAD_JOURNAL_FILE_NAME: &str = "uploads.json";
+pub const FILE_METADATA_FILE_NAME: &str = "files.json";
+pub const SYNC_MANIFEST_FILE_NAME: &str = "sync.json";
 
 //why we made it this .shcignore?
 pub const SHC_IGNORE_FILE_NAME: &str = ".shcignore";
 
 pub const MAX_NAME_WIDTH_LENGTH: usize = 50;
+
+// files fetched per request when listing
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+
+// files of at least this size are uploaded in parts, so a failed upload can be resumed
+pub const MULTIPART_UPLOAD_THRESHOLD: u64 = 64 * 1024 * 1024;
+pub const MULTIPART_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+// defaults of the retries and timeouts set in config.toml or with --retries and --timeout
+// a retried request or a dropped download gets this many more attempts
+pub const DEFAULT_RETRIES: u32 = 3;
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+// longest wait between two attempts, also for a server's Retry-After
+pub const MAX_RETRY_DELAY_SECS: u64 = 30;
+
+// changes to a watched file within this many milliseconds are uploaded together
+pub const WATCH_DEBOUNCE_MS: u64 = 500;
+
+// files acted on at the same time by remove, visibility and get with several files
+pub const BULK_JOBS: usize = 4;
+
+// an access token expiring within this many seconds is refreshed before it is sent
+pub const TOKEN_REFRESH_MARGIN_SECS: i64 = 60;
+
+// tokens are kept in the OS keyring under this service, or in this file next to config.toml
+pub const KEYRING_SERVICE: &str = "shc-cli";
+pub const CREDENTIALS_FILE_NAME: &str = "credentials.toml";
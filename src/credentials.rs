@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::consts::{CREDENTIALS_FILE_NAME, KEYRING_SERVICE};
+
+// the secret part of a profile, never written to config.toml
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct Credentials {
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+impl Credentials {
+    pub fn is_empty(&self) -> bool {
+        self.access_token.is_none() && self.refresh_token.is_none()
+    }
+}
+
+// where the credentials of every profile are kept
+pub trait CredentialStore: Debug + Send + Sync {
+    fn load(&self, profile: &str) -> io::Result<Option<Credentials>>;
+    fn save(&self, profile: &str, credentials: &Credentials) -> io::Result<()>;
+    fn remove(&self, profile: &str) -> io::Result<()>;
+}
+
+// the keyring of the OS when there is one, the credentials file otherwise
+// SHC_CREDENTIAL_STORE=file always uses the file, e.g. when the keyring prompts on every access
+pub fn open(shc_folder: &Path) -> Arc<dyn CredentialStore> {
+    let file_store = || Arc::new(FileStore::new(shc_folder.join(CREDENTIALS_FILE_NAME)));
+    if std::env::var("SHC_CREDENTIAL_STORE").as_deref() == Ok("file") {
+        return file_store();
+    }
+    match KeyringStore::open() {
+        Some(keyring) => Arc::new(keyring),
+        None => file_store(),
+    }
+}
+
+// Keychain on macOS, Credential Manager on Windows and the Secret Service on Linux
+// the credentials of a profile are one entry holding them as JSON
+#[derive(Debug)]
+pub struct KeyringStore;
+
+impl KeyringStore {
+    // None when there is no keyring to talk to, e.g. on a server without a desktop session
+    pub fn open() -> Option<Self> {
+        // reading an entry that may not exist tells if the keyring answers at all
+        let entry = keyring::Entry::new(KEYRING_SERVICE, "probe").ok()?;
+        match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Some(KeyringStore),
+            Err(_) => None,
+        }
+    }
+
+    fn entry(profile: &str) -> io::Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, profile).map_err(keyring_error)
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn load(&self, profile: &str) -> io::Result<Option<Credentials>> {
+        match Self::entry(profile)?.get_password() {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+
+    fn save(&self, profile: &str, credentials: &Credentials) -> io::Result<()> {
+        let json = serde_json::to_string(credentials)?;
+        Self::entry(profile)?
+            .set_password(&json)
+            .map_err(keyring_error)
+    }
+
+    fn remove(&self, profile: &str) -> io::Result<()> {
+        match Self::entry(profile)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+}
+
+fn keyring_error(e: keyring::Error) -> io::Error {
+    io::Error::other(format!("keyring: {}", e))
+}
+
+// layout of credentials.toml, one table per profile like config.toml
+#[derive(Deserialize, Serialize, Default)]
+struct CredentialsFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, Credentials>,
+}
+
+// credentials.toml, only readable by its owner
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf) -> Self {
+        FileStore { path }
+    }
+
+    fn read(&self) -> io::Result<CredentialsFile> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(CredentialsFile::default()),
+            Err(e) => return Err(e),
+        };
+        self.check_permissions();
+        toml::from_str(&contents).map_err(io::Error::other)
+    }
+
+    fn write(&self, file: &CredentialsFile) -> io::Result<()> {
+        let toml = toml::to_string(file).map_err(io::Error::other)?;
+        let folder = self.path.parent().unwrap_or(Path::new("."));
+        // temporary files are only readable by their owner, renaming one into place also
+        // replaces a file with looser permissions and never leaves a half written one
+        let mut temp_file = tempfile::Builder::new()
+            .prefix(".credentials")
+            .tempfile_in(folder)?;
+        temp_file.write_all(toml.as_bytes())?;
+        temp_file.as_file().sync_all()?;
+        temp_file.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    // warns once if someone besides the owner can read the tokens
+    fn check_permissions(&self) {
+        #[cfg(unix)]
+        {
+            use console::style;
+            use std::os::unix::fs::PermissionsExt;
+            use std::sync::Once;
+
+            static WARNED: Once = Once::new();
+            let Ok(metadata) = fs::metadata(&self.path) else {
+                return;
+            };
+            if metadata.permissions().mode() & 0o077 != 0 {
+                WARNED.call_once(|| {
+                    eprintln!(
+                        "{} {} can be read by other users, run `chmod 600 {}`",
+                        style("warning:").yellow().bold(),
+                        self.path.display(),
+                        self.path.display()
+                    );
+                });
+            }
+        }
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn load(&self, profile: &str) -> io::Result<Option<Credentials>> {
+        Ok(self.read()?.profiles.remove(profile))
+    }
+
+    fn save(&self, profile: &str, credentials: &Credentials) -> io::Result<()> {
+        let mut file = self.read()?;
+        file.profiles
+            .insert(profile.to_string(), credentials.clone());
+        self.write(&file)
+    }
+
+    fn remove(&self, profile: &str) -> io::Result<()> {
+        let mut file = self.read()?;
+        if file.profiles.remove(profile).is_some() {
+            self.write(&file)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(access_token: &str) -> Credentials {
+        Credentials {
+            access_token: Some(access_token.to_string()),
+            refresh_token: Some(format!("{}-refresh", access_token)),
+        }
+    }
+
+    #[test]
+    fn file_store_round_trip() {
+        let folder = tempfile::tempdir().unwrap();
+        let store = FileStore::new(folder.path().join(CREDENTIALS_FILE_NAME));
+        assert!(store.load("default").unwrap().is_none());
+
+        store.save("default", &credentials("a")).unwrap();
+        store.save("work", &credentials("b")).unwrap();
+        let loaded = store.load("default").unwrap().unwrap();
+        assert_eq!(loaded.access_token.as_deref(), Some("a"));
+        assert_eq!(loaded.refresh_token.as_deref(), Some("a-refresh"));
+
+        store.remove("default").unwrap();
+        assert!(store.load("default").unwrap().is_none());
+        let work = store.load("work").unwrap().unwrap();
+        assert_eq!(work.access_token.as_deref(), Some("b"));
+        // removing what is not there is fine
+        store.remove("default").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_store_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join(CREDENTIALS_FILE_NAME);
+        let store = FileStore::new(path.clone());
+        let mode = || fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+
+        store.save("default", &credentials("a")).unwrap();
+        assert_eq!(mode(), 0o600);
+
+        // a file made readable by others gets its permissions back on the next write
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        store.save("default", &credentials("b")).unwrap();
+        assert_eq!(mode(), 0o600);
+        assert_eq!(
+            store
+                .load("default")
+                .unwrap()
+                .unwrap()
+                .access_token
+                .as_deref(),
+            Some("b")
+        );
+        // nothing is left behind next to it
+        assert_eq!(fs::read_dir(folder.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bytes::Bytes;
+use chacha20poly1305::aead::stream::{NewStream, StreamBE32, StreamPrimitive};
+use chacha20poly1305::aead::{KeyInit, OsRng};
+use chacha20poly1305::ChaCha20Poly1305;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+use tokio_stream::Stream;
+
+use crate::error::ShcError;
+
+// files encrypted by `shc add --encrypt` start with this
+const MAGIC: &[u8] = b"shc-enc1";
+// the plaintext is encrypted in chunks of this size, each followed by its tag
+const CHUNK_SIZE: u64 = 64 * 1024;
+const TAG_SIZE: u64 = 16;
+
+// random key of one encrypted file, it only ever travels in the fragment of the share link
+#[derive(Clone)]
+pub struct FileKey([u8; 32]);
+
+impl FileKey {
+    pub fn generate() -> Self {
+        FileKey(ChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    // browsers and http clients never send the `#...` part of a link to the server
+    pub fn to_fragment(&self) -> String {
+        format!("key={}", URL_SAFE_NO_PAD.encode(self.0))
+    }
+
+    // key from a share link like `https://.../share/<id>#key=...`, None if the link has none
+    pub fn from_share_link(link: &str) -> Result<Option<FileKey>, ShcError> {
+        let key = match link.split_once('#') {
+            Some((_, fragment)) => fragment
+                .split('&')
+                .find_map(|param| param.strip_prefix("key=")),
+            None => None,
+        };
+        let Some(key) = key else {
+            return Ok(None);
+        };
+
+        URL_SAFE_NO_PAD
+            .decode(key)
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .map(|key| Some(FileKey(key)))
+            .ok_or_else(|| ShcError::Usage("the key in the share link is invalid".to_string()))
+    }
+
+    // every file has its own random key, so the fixed nonce is never reused with a key
+    fn stream(&self) -> StreamBE32<ChaCha20Poly1305> {
+        StreamBE32::new(&self.0.into(), &Default::default())
+    }
+}
+
+fn chunk_count(plain_size: u64) -> u64 {
+    // an empty file still has one (empty) last chunk
+    plain_size.div_ceil(CHUNK_SIZE).max(1)
+}
+
+pub fn encrypted_size(plain_size: u64) -> u64 {
+    MAGIC.len() as u64 + plain_size + chunk_count(plain_size) * TAG_SIZE
+}
+
+fn encrypt_chunk(
+    stream: &StreamBE32<ChaCha20Poly1305>,
+    position: u64,
+    last: bool,
+    chunk: &mut Vec<u8>,
+) -> io::Result<()> {
+    stream
+        .encrypt_in_place(position as u32, last, b"", chunk)
+        .map_err(|_| io::Error::other("encryption failed"))
+}
+
+// encrypts the file while it is read, for the body of the upload
+pub fn encrypt_stream(
+    mut file: tokio::fs::File,
+    plain_size: u64,
+    key: FileKey,
+) -> impl Stream<Item = io::Result<Bytes>> {
+    async_stream::try_stream! {
+        yield Bytes::from_static(MAGIC);
+
+        let stream = key.stream();
+        let chunks = chunk_count(plain_size);
+        for position in 0..chunks {
+            let len = CHUNK_SIZE.min(plain_size - position * CHUNK_SIZE);
+            let mut chunk = vec![0; len as usize];
+            file.read_exact(&mut chunk).await?;
+            encrypt_chunk(&stream, position, position + 1 == chunks, &mut chunk)?;
+            yield Bytes::from(chunk);
+        }
+    }
+}
+
+// SHA-256 of the encrypted file as it will be uploaded, the server never sees the plaintext hash
+pub fn encrypted_sha256(file_path: &Path, key: &FileKey) -> io::Result<String> {
+    let mut file = File::open(file_path)?;
+    let plain_size = file.metadata()?.len();
+    let mut hasher = Sha256::new();
+    hasher.update(MAGIC);
+
+    let stream = key.stream();
+    let chunks = chunk_count(plain_size);
+    for position in 0..chunks {
+        let len = CHUNK_SIZE.min(plain_size - position * CHUNK_SIZE);
+        let mut chunk = vec![0; len as usize];
+        file.read_exact(&mut chunk)?;
+        encrypt_chunk(&stream, position, position + 1 == chunks, &mut chunk)?;
+        hasher.update(&chunk);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub async fn encrypted_sha256_async(file_path: &Path, key: &FileKey) -> io::Result<String> {
+    let file_path = file_path.to_path_buf();
+    let key = key.clone();
+    tokio::task::spawn_blocking(move || encrypted_sha256(&file_path, &key))
+        .await
+        .map_err(io::Error::other)?
+}
+
+// where to continue an encrypted download that already wrote `plain_len` bytes of plaintext
+// returns the plaintext length to keep, the offset in the encrypted file to request from
+// and the chunk found at that offset
+pub fn resume_point(plain_len: u64) -> (u64, u64, u64) {
+    // the chunk written last is fetched again, it may be the last one and must be decrypted as such
+    let chunks = plain_len.saturating_sub(1) / CHUNK_SIZE;
+    if chunks == 0 {
+        return (0, 0, 0);
+    }
+    let offset = MAGIC.len() as u64 + chunks * (CHUNK_SIZE + TAG_SIZE);
+    (chunks * CHUNK_SIZE, offset, chunks)
+}
+
+// decrypts everything written to it into `inner`, call finish() once the whole file was written
+// `position` is the chunk the written data starts at, 0 when it starts at the beginning of the file
+pub struct DecryptWriter<W: Write> {
+    inner: W,
+    stream: StreamBE32<ChaCha20Poly1305>,
+    position: u64,
+    magic_checked: bool,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> DecryptWriter<W> {
+    pub fn new(inner: W, key: &FileKey, position: u64) -> Self {
+        DecryptWriter {
+            inner,
+            stream: key.stream(),
+            position,
+            magic_checked: position > 0,
+            buf: vec![],
+        }
+    }
+
+    fn decrypt_chunk(&mut self, len: usize, last: bool) -> io::Result<()> {
+        let mut chunk: Vec<u8> = self.buf.drain(..len).collect();
+        self.stream
+            .decrypt_in_place(self.position as u32, last, b"", &mut chunk)
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "decryption failed, the key is wrong or the file was modified",
+                )
+            })?;
+        self.position += 1;
+        self.inner.write_all(&chunk)
+    }
+
+    // decrypts the last chunk, which can only be told apart once nothing follows it
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.magic_checked || self.buf.len() < TAG_SIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the encrypted file is incomplete",
+            ));
+        }
+        self.decrypt_chunk(self.buf.len(), true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for DecryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+
+        if !self.magic_checked {
+            if self.buf.len() < MAGIC.len() {
+                return Ok(buf.len());
+            }
+            if !self.buf.starts_with(MAGIC) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the file was not encrypted by shc, remove the key from the link",
+                ));
+            }
+            self.buf.drain(..MAGIC.len());
+            self.magic_checked = true;
+        }
+
+        // a full chunk is only known not to be the last one once more data follows it
+        let chunk_len = (CHUNK_SIZE + TAG_SIZE) as usize;
+        while self.buf.len() > chunk_len {
+            self.decrypt_chunk(chunk_len, false)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+
+#[derive(Debug)]
+pub enum ShcError {
+    // request could not be sent or the connection broke
+    Network(reqwest::Error),
+    // server answered with an unexpected status
+    Http { status: StatusCode, message: String },
+    // response or local file could not be parsed
+    Deserialize(String),
+    // refresh token is no longer accepted, user has to login again
+    AuthExpired,
+    Io(std::io::Error),
+    // user answered "no" to a prompt
+    Aborted,
+    // Ctrl-C
+    Interrupted,
+    // downloaded or local file differs from what was uploaded
+    ChecksumMismatch { expected: String, actual: String },
+    // missing argument that would otherwise be asked for interactively
+    Usage(String),
+}
+
+impl ShcError {
+    // builds an Http error from a non-success response, using the message sent by the server
+    pub async fn from_response(res: reqwest::Response) -> ShcError {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+
+        let message = match serde_json::from_str::<Value>(&body) {
+            Ok(json) => ["message", "error", "detail"]
+                .iter()
+                .find_map(|key| json.get(key).and_then(Value::as_str))
+                .map(str::to_string),
+            Err(_) => None,
+        }
+        .or_else(|| {
+            let body = body.trim();
+            (!body.is_empty() && body.len() <= 200).then(|| body.to_string())
+        })
+        .unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("Something went wrong")
+                .to_string()
+        });
+
+        ShcError::Http { status, message }
+    }
+
+    // process exit code, one per error class so scripts can tell them apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ShcError::Network(_) => 3,
+            ShcError::Http { .. } => 4,
+            ShcError::Deserialize(_) => 5,
+            ShcError::AuthExpired => 6,
+            ShcError::Io(_) => 7,
+            ShcError::Aborted => 8,
+            ShcError::Interrupted => 130,
+            ShcError::ChecksumMismatch { .. } => 9,
+            ShcError::Usage(_) => 2,
+        }
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ShcError::Network(_) => Some("check your internet connection or the profile's API URL"),
+            ShcError::Http { status, .. } if *status == StatusCode::NOT_FOUND => {
+                Some("the file may have been removed, run `shc list` to see your files")
+            }
+            ShcError::AuthExpired => Some("run `shc login` and try again"),
+            ShcError::Usage(_) => Some("run the command with --help to see its options"),
+            ShcError::ChecksumMismatch { .. } => {
+                Some("the file was corrupted or changed, download or upload it again")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ShcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShcError::Network(e) => write!(f, "network error: {}", e),
+            ShcError::Http { status, message } => {
+                write!(f, "server responded with {}: {}", status.as_u16(), message)
+            }
+            ShcError::Deserialize(e) => write!(f, "unexpected response: {}", e),
+            ShcError::AuthExpired => write!(f, "session expired, please login again"),
+            ShcError::Io(e) => write!(f, "{}", e),
+            ShcError::Aborted => write!(f, "aborted"),
+            ShcError::Interrupted => write!(f, "interrupted"),
+            ShcError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected sha256 {}, got {}",
+                expected, actual
+            ),
+            ShcError::Usage(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ShcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ShcError::Network(e) => Some(e),
+            ShcError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ShcError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            ShcError::Deserialize(e.to_string())
+        } else {
+            ShcError::Network(e)
+        }
+    }
+}
+
+impl From<std::io::Error> for ShcError {
+    fn from(e: std::io::Error) -> Self {
+        ShcError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ShcError {
+    fn from(e: serde_json::Error) -> Self {
+        ShcError::Deserialize(e.to_string())
+    }
+}
+
+impl From<chrono::ParseError> for ShcError {
+    fn from(e: chrono::ParseError) -> Self {
+        ShcError::Deserialize(e.to_string())
+    }
+}
+
+impl From<dialoguer::Error> for ShcError {
+    fn from(e: dialoguer::Error) -> Self {
+        match e {
+            dialoguer::Error::IO(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                ShcError::Interrupted
+            }
+            dialoguer::Error::IO(e) => ShcError::Io(e),
+        }
+    }
+}
+
+impl From<csv::Error> for ShcError {
+    fn from(e: csv::Error) -> Self {
+        match e.into_kind() {
+            csv::ErrorKind::Io(e) => ShcError::Io(e),
+            kind => ShcError::Deserialize(format!("{:?}", kind)),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::consts::FILE_METADATA_FILE_NAME;
+use crate::models::ShareLimits;
+use crate::state_file;
+
+// what `shc prune` needs to know about a file uploaded with --expires-in or --max-downloads
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct FileMetadata {
+    pub profile: String,
+    pub file_name: String,
+    #[serde(flatten)]
+    pub limits: ShareLimits,
+}
+
+impl FileMetadata {
+    // why the file should be removed, None while it is still within its limits
+    pub fn expired_reason(&self, download_count: Option<u64>) -> Option<&'static str> {
+        let expired = self
+            .limits
+            .expires_at
+            .as_deref()
+            .and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok())
+            .is_some_and(|expires_at| expires_at < Utc::now());
+        if expired {
+            return Some("expired");
+        }
+
+        match (self.limits.max_downloads, download_count) {
+            (Some(max_downloads), Some(count)) if count >= max_downloads => {
+                Some("download limit reached")
+            }
+            _ => None,
+        }
+    }
+}
+
+// every tracked file, keyed by file id
+// a missing or broken file only means nothing expires
+pub fn load() -> BTreeMap<String, FileMetadata> {
+    state_file::load(&metadata_path())
+}
+
+pub fn insert(file_id: &str, metadata: FileMetadata) -> std::io::Result<()> {
+    state_file::update(&metadata_path(), |entries: &mut BTreeMap<_, _>| {
+        entries.insert(file_id.to_string(), metadata);
+    })
+}
+
+pub fn remove(file_ids: &[String]) -> std::io::Result<()> {
+    state_file::update(
+        &metadata_path(),
+        |entries: &mut BTreeMap<_, FileMetadata>| {
+            entries.retain(|file_id, _| !file_ids.contains(file_id));
+        },
+    )
+}
+
+fn metadata_path() -> PathBuf {
+    state_file::state_path(FILE_METADATA_FILE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn metadata(expires_at: Option<DateTime<Utc>>, max_downloads: Option<u64>) -> FileMetadata {
+        FileMetadata {
+            profile: "default".to_string(),
+            file_name: "report.pdf".to_string(),
+            limits: ShareLimits {
+                expires_at: expires_at.map(|expires_at| expires_at.to_rfc3339()),
+                max_downloads,
+            },
+        }
+    }
+
+    #[test]
+    fn expires_after_its_time() {
+        let past = metadata(Some(Utc::now() - Duration::hours(1)), None);
+        assert_eq!(past.expired_reason(None), Some("expired"));
+
+        let future = metadata(Some(Utc::now() + Duration::hours(1)), None);
+        assert_eq!(future.expired_reason(None), None);
+    }
+
+    #[test]
+    fn expires_at_its_download_limit() {
+        let limited = metadata(None, Some(3));
+        assert_eq!(limited.expired_reason(Some(2)), None);
+        assert_eq!(
+            limited.expired_reason(Some(3)),
+            Some("download limit reached")
+        );
+        assert_eq!(
+            limited.expired_reason(Some(4)),
+            Some("download limit reached")
+        );
+        // a count the server did not report never expires the file
+        assert_eq!(limited.expired_reason(None), None);
+    }
+
+    #[test]
+    fn expiry_is_reported_before_the_download_limit() {
+        let both = metadata(Some(Utc::now() - Duration::hours(1)), Some(1));
+        assert_eq!(both.expired_reason(Some(5)), Some("expired"));
+    }
+
+    #[test]
+    fn without_limits_never_expires() {
+        let unlimited = metadata(None, None);
+        assert_eq!(unlimited.expired_reason(Some(1000)), None);
+
+        let unreadable = FileMetadata {
+            limits: ShareLimits {
+                expires_at: Some("not a date".to_string()),
+                max_downloads: None,
+            },
+            ..unlimited
+        };
+        assert_eq!(unreadable.expired_reason(None), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use tempfile::NamedTempFile;
+
+use crate::error::ShcError;
+use crate::interrupt::TempArtifact;
+
+// part of a file selected with `--lines` or `--bytes`
+// lines are 1-based and inclusive, bytes are 0-based and exclusive at the end
+// a missing end means "until the end of the file"
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FileRange {
+    Lines { start: u64, end: Option<u64> },
+    Bytes { start: u64, end: Option<u64> },
+}
+
+// value parser for `--lines 120:180`
+pub fn parse_lines(arg: &str) -> Result<FileRange, String> {
+    let (start, end) = parse_bounds(arg)?;
+    let start = start.unwrap_or(1);
+    if start == 0 {
+        return Err("line numbers start at 1".to_string());
+    }
+    Ok(FileRange::Lines { start, end })
+}
+
+// value parser for `--bytes 0:4096`
+pub fn parse_bytes(arg: &str) -> Result<FileRange, String> {
+    let (start, end) = parse_bounds(arg)?;
+    let start = start.unwrap_or(0);
+    if end == Some(start) {
+        return Err("byte range is empty".to_string());
+    }
+    Ok(FileRange::Bytes { start, end })
+}
+
+fn parse_bounds(arg: &str) -> Result<(Option<u64>, Option<u64>), String> {
+    let (start, end) = arg
+        .split_once(':')
+        .ok_or_else(|| "expected START:END, e.g. 120:180".to_string())?;
+
+    let parse = |bound: &str| -> Result<Option<u64>, String> {
+        let bound = bound.trim();
+        if bound.is_empty() {
+            return Ok(None);
+        }
+        bound
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("`{}` is not a number", bound))
+    };
+
+    let (start, end) = (parse(start)?, parse(end)?);
+    if let (Some(start), Some(end)) = (start, end) {
+        if end < start {
+            return Err(format!("range end {} is before its start {}", end, start));
+        }
+    }
+    Ok((start, end))
+}
+
+// copies the selected ranges of file_path, in the given order, into a temp file
+// returns it with a name suffix like `#L120-180`, open ends resolved to the real end of the file
+pub fn write_slice(
+    file_path: &Path,
+    ranges: &[FileRange],
+) -> Result<(TempArtifact, String), ShcError> {
+    let (mut slice_file, slice_path) = NamedTempFile::new()?.keep().map_err(|e| e.error)?;
+    // removed on Ctrl-C too, not only when the upload is done with it
+    let slice = TempArtifact::new(slice_path);
+    let mut labels = vec![];
+
+    for range in ranges {
+        let label = match *range {
+            FileRange::Lines { start, end } => {
+                match copy_lines(file_path, start, end, &mut slice_file)? {
+                    end if end == start => format!("L{}", start),
+                    end => format!("L{}-{}", start, end),
+                }
+            }
+            FileRange::Bytes { start, end } => {
+                let end = copy_bytes(file_path, start, end, &mut slice_file)?;
+                format!("B{}-{}", start, end)
+            }
+        };
+        labels.push(label);
+    }
+    slice_file.flush()?;
+
+    Ok((slice, format!("#{}", labels.join(","))))
+}
+
+// returns the number of the last copied line
+fn copy_lines(
+    file_path: &Path,
+    start: u64,
+    end: Option<u64>,
+    dest: &mut File,
+) -> Result<u64, ShcError> {
+    let mut reader = BufReader::new(File::open(file_path)?);
+    let mut line = vec![];
+    let mut line_number = 0;
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        if line_number >= start {
+            dest.write_all(&line)?;
+        }
+        if Some(line_number) == end {
+            break;
+        }
+    }
+
+    if line_number < start {
+        return Err(ShcError::Usage(format!(
+            "line {} is past the end of {} ({} lines)",
+            start,
+            file_path.display(),
+            line_number
+        )));
+    }
+    Ok(line_number)
+}
+
+// returns the end offset of the copied bytes
+fn copy_bytes(
+    file_path: &Path,
+    start: u64,
+    end: Option<u64>,
+    dest: &mut File,
+) -> Result<u64, ShcError> {
+    let mut file = File::open(file_path)?;
+    let file_size = file.metadata()?.len();
+    if start >= file_size {
+        return Err(ShcError::Usage(format!(
+            "byte {} is past the end of {} ({} bytes)",
+            start,
+            file_path.display(),
+            file_size
+        )));
+    }
+
+    let end = end.map_or(file_size, |end| end.min(file_size));
+    file.seek(SeekFrom::Start(start))?;
+    io::copy(&mut file.take(end - start), dest)?;
+    Ok(end)
+}
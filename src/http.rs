@@ -0,0 +1,192 @@
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::consts::{
+    DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_RETRIES, DEFAULT_TIMEOUT_SECS, MAX_RETRY_DELAY_SECS,
+};
+use crate::error::ShcError;
+use crate::interrupt;
+use crate::user_config::UserInfo;
+
+// timeouts and retries of every request, set per profile in config.toml or with the global flags
+#[derive(Clone, Copy, Debug)]
+pub struct HttpSettings {
+    // extra attempts for a request that can safely be sent again
+    pub retries: u32,
+    pub connect_timeout: Duration,
+    // for a whole API call, and for the gap between two chunks of a download
+    pub timeout: Duration,
+}
+
+impl HttpSettings {
+    pub fn new(user: &UserInfo) -> Self {
+        HttpSettings {
+            retries: user.retries.unwrap_or(DEFAULT_RETRIES),
+            connect_timeout: Duration::from_secs(
+                user.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+            ),
+            timeout: Duration::from_secs(user.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+        }
+    }
+
+    // for the backend API, its answers are small so the timeout covers the whole call
+    pub fn api_client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .build()
+            .expect("client without custom TLS settings always builds")
+    }
+
+    // for uploads and downloads, they take as long as they take while data is flowing
+    pub fn transfer_client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .build()
+            .expect("client without custom TLS settings always builds")
+    }
+}
+
+// sends the request built by `request` and sends it again after connection errors, timeouts,
+// 429 and 5xx answers, only for requests that do the same thing when repeated
+// returns the last response, so an answer that is still failing is reported like any other
+pub async fn send(
+    settings: &HttpSettings,
+    request: impl Fn() -> RequestBuilder,
+) -> Result<Response, ShcError> {
+    let mut attempt = 0;
+
+    loop {
+        let res = request().send().await;
+        let retry_after = match &res {
+            Ok(res) if is_transient_status(res.status()) => retry_after(res),
+            Err(e) if is_transient_network(e) => None,
+            _ => return Ok(res?),
+        };
+        // a server asking for a longer break than we are willing to wait gets its answer reported
+        let delay = match retry_after {
+            Some(delay) if delay > Duration::from_secs(MAX_RETRY_DELAY_SECS) => None,
+            Some(delay) => Some(delay),
+            None => Some(backoff(attempt)),
+        };
+        let Some(delay) = delay.filter(|_| attempt < settings.retries) else {
+            return Ok(res?);
+        };
+
+        attempt += 1;
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = interrupt::cancelled() => return Err(ShcError::Interrupted),
+        }
+    }
+}
+
+// whether another attempt could work, the connection broke or nothing arrived in time
+pub fn is_transient(e: &ShcError) -> bool {
+    match e {
+        ShcError::Network(e) => is_transient_network(e),
+        ShcError::Io(e) => matches!(
+            e.kind(),
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::UnexpectedEof
+        ),
+        _ => false,
+    }
+}
+
+// is_request() is left out, it also covers requests that can't be sent at all,
+// like an upload whose local file can't be read, and those fail the same way every time
+fn is_transient_network(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_body()
+}
+
+// wait before retry number `attempt` counting from 0, doubling from half a second up to
+// MAX_RETRY_DELAY_SECS with random jitter, so many clients failing at once don't retry at once
+pub fn backoff(attempt: u32) -> Duration {
+    let max = Duration::from_secs(MAX_RETRY_DELAY_SECS);
+    let base = Duration::from_millis(500)
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(max);
+    // a fresh RandomState is randomly seeded, enough randomness for jitter without a dependency
+    let random = RandomState::new().build_hasher().finish();
+    base / 2 + base.mul_f64((random % 1000) as f64 / 2000.0)
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+// `Retry-After: 120` or `Retry-After: Wed, 21 Oct 2015 07:28:00 GMT`
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.signed_duration_since(chrono::Utc::now());
+    Some(delay.to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, TestServer};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn settings() -> HttpSettings {
+        HttpSettings {
+            retries: 1,
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    async fn attempts(request: impl Fn(&reqwest::Client) -> RequestBuilder) -> u32 {
+        let attempts = AtomicU32::new(0);
+        let client = settings().api_client();
+        let res = send(&settings(), || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            request(&client)
+        })
+        .await;
+        assert!(res.is_err());
+        attempts.into_inner()
+    }
+
+    #[tokio::test]
+    async fn refused_connection_is_retried() {
+        // bound and dropped, nothing listens there anymore
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{}/", port);
+        assert_eq!(attempts(|client| client.get(&url)).await, 2);
+    }
+
+    #[tokio::test]
+    async fn failing_request_body_is_not_retried() {
+        let server = TestServer::start(|_| Reply::status(200)).await;
+        // like an upload whose local file can't be read
+        let body = || {
+            let stream =
+                tokio_stream::once(Err::<bytes::Bytes, _>(std::io::Error::other("unreadable")));
+            reqwest::Body::wrap_stream(stream)
+        };
+        assert_eq!(
+            attempts(|client| client.put(&server.url).body(body())).await,
+            1
+        );
+    }
+}
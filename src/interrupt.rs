@@ -0,0 +1,115 @@
+use console::{style, Term};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::ShcError;
+
+// how long a transfer gets to clean up after Ctrl-C before the process exits anyway
+const CLEANUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+static CANCEL: OnceLock<CancellationToken> = OnceLock::new();
+static CLEANUP_GUARDS: AtomicUsize = AtomicUsize::new(0);
+static TEMP_ARTIFACTS: Mutex<Vec<PathBuf>> = Mutex::new(vec![]);
+
+fn token() -> &'static CancellationToken {
+    CANCEL.get_or_init(CancellationToken::new)
+}
+
+// handles Ctrl-C for the rest of the process
+// without a CleanupGuard alive the process exits right away,
+// otherwise the guard holder is cancelled and gets some time to clean up, a second Ctrl-C exits
+pub fn listen() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        token().cancel();
+
+        if CLEANUP_GUARDS.load(Ordering::SeqCst) > 0 {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = tokio::time::sleep(CLEANUP_TIMEOUT) => {}
+            }
+        }
+        exit();
+    });
+}
+
+// resolves once Ctrl-C was pressed, race it against a transfer with tokio::select!
+pub async fn cancelled() {
+    token().cancelled().await
+}
+
+// removes temp artifacts, restores the terminal and exits like a shell would after SIGINT
+pub fn exit() -> ! {
+    restore_terminal();
+    remove_temp_artifacts();
+    eprintln!("{} {}", style("error:").red().bold(), ShcError::Interrupted);
+    std::process::exit(ShcError::Interrupted.exit_code());
+}
+
+// dialoguer hides the cursor while a prompt is open and does not show it again when interrupted
+pub fn restore_terminal() {
+    let term = Term::stderr();
+    if term.is_term() {
+        let _ = term.show_cursor();
+    }
+}
+
+pub fn remove_temp_artifacts() {
+    let paths = std::mem::take(&mut *TEMP_ARTIFACTS.lock().unwrap());
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// keeps Ctrl-C from exiting immediately while alive, see listen()
+pub struct CleanupGuard;
+
+impl CleanupGuard {
+    pub fn new() -> Self {
+        CLEANUP_GUARDS.fetch_add(1, Ordering::SeqCst);
+        CleanupGuard
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        CLEANUP_GUARDS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// file that is removed when dropped, or on exit if the process is interrupted before that
+// e.g. the zip of a folder being uploaded
+pub struct TempArtifact {
+    path: PathBuf,
+}
+
+impl TempArtifact {
+    pub fn new(path: PathBuf) -> Self {
+        TEMP_ARTIFACTS.lock().unwrap().push(path.clone());
+        TempArtifact { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempArtifact {
+    fn drop(&mut self) {
+        forget(&self.path);
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+pub fn is_temp_artifact(path: &Path) -> bool {
+    TEMP_ARTIFACTS.lock().unwrap().iter().any(|p| p == path)
+}
+
+fn forget(path: &Path) {
+    TEMP_ARTIFACTS.lock().unwrap().retain(|p| p != path);
+}
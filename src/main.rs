@@ -1,86 +1,333 @@
 mod api_client;
+mod checksum;
 mod cli;
 mod command;
 mod consts;
+mod credentials;
+mod encryption;
+mod error;
+mod file_metadata;
+mod file_slice;
+mod http;
+mod interrupt;
 mod models;
+mod output;
+mod state_file;
+mod sync_manifest;
+#[cfg(test)]
+mod test_server;
 mod tui;
+mod upload_journal;
 mod user_config;
 mod utils;
 
-use std::path::PathBuf;
+use clap::ArgMatches;
+use console::style;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::api_client::ApiClient;
+use crate::error::ShcError;
+use crate::file_slice::FileRange;
+use crate::http::HttpSettings;
+use crate::models::ShareLimits;
+use crate::output::OutputFormat;
 
 // read them and understand them
-use crate::command::auth::{check_for_api_key, login, logout};
+use crate::command::auth::{check_for_api_key, login, logout, print_login};
 use crate::user_config::UserConfig;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut user_config = UserConfig::new();
+async fn main() {
     let matches = cli::cli().get_matches();
+    interrupt::listen();
 
-    match matches.subcommand() {
-        Some(("login", _)) => login(&mut user_config).await?,
-        Some(("logout", _)) => logout(&mut user_config),
-        None => println!("No subcommand was used"),
+    if let Err(e) = run(&matches).await {
+        interrupt::restore_terminal();
+        eprintln!("{} {}", style("error:").red().bold(), e);
+        if let Some(hint) = e.hint() {
+            eprintln!("{} {}", style("hint:").cyan().bold(), hint);
+        }
+        std::process::exit(e.exit_code());
+    }
+}
+
+async fn run(matches: &ArgMatches) -> Result<(), ShcError> {
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    let mut user_config = UserConfig::new(profile);
+    let format = OutputFormat::from_arg(
+        matches
+            .get_one::<String>("output")
+            .map(String::as_str)
+            .unwrap_or_default(),
+    );
+
+    // a typo in --profile would otherwise quietly use the defaults
+    // `login` and `profile set` create the profile, the other profile commands don't use it
+    let creates_profile = matches!(matches.subcommand_name(), Some("login" | "profile"));
+    if !creates_profile && !user_config.profile_exists() {
+        return Err(ShcError::Usage(format!(
+            "unknown profile \"{}\", see `shc profile list` or create it with `shc --profile {} login`",
+            user_config.profile, user_config.profile
+        )));
+    }
 
+    let http = http_settings(matches, &user_config);
+
+    match matches.subcommand() {
+        Some(("login", _)) => {
+            login(&mut user_config, http).await?;
+            print_login(&user_config, format)?;
+        }
+        Some(("logout", _)) => logout(&mut user_config, format)?,
+        Some(("profile", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", _)) => command::profile::list_profiles(&user_config, format)?,
+            Some(("set", set_matches)) => command::profile::set_profile(
+                &mut user_config,
+                set_matches.get_one::<String>("api-url"),
+                set_matches.get_one::<String>("frontend-url"),
+                format,
+            )?,
+            Some(("remove", remove_matches)) => {
+                let name = remove_matches.get_one::<String>("NAME").expect("required");
+                command::profile::remove_profile(&mut user_config, name, format)?;
+            }
+            _ => println!("Command not found."),
+        },
         _ => {
-            check_for_api_key(&mut user_config).await?;
-            let mut api_client = ApiClient::new(user_config);
+            check_for_api_key(&mut user_config, http).await?;
+            let mut api_client = ApiClient::new(user_config, http);
             match matches.subcommand() {
                 Some(("add", sub_matches)) => {
-                    let file = sub_matches.get_one::<String>("FILE").expect("required");
-                    let file_path = PathBuf::from(file);
-                    if !file_path.exists() {
-                        println!("File not found");
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::NotFound,
-                            "File not found",
-                        )
-                        .into());
+                    let files: Vec<String> = sub_matches
+                        .get_many::<String>("FILE")
+                        .expect("required")
+                        .cloned()
+                        .collect();
+                    let options = command::add::AddOptions {
+                        resume: sub_matches.get_flag("resume"),
+                        name: sub_matches.get_one::<String>("name").cloned(),
+                        mime_type: sub_matches.get_one::<String>("mime").cloned(),
+                        ranges: ["lines", "bytes"]
+                            .iter()
+                            .flat_map(|id| sub_matches.get_many::<FileRange>(id))
+                            .flatten()
+                            .copied()
+                            .collect(),
+                        encrypt: sub_matches.get_flag("encrypt"),
+                        public: visibility_flag(sub_matches),
+                        limits: ShareLimits {
+                            expires_at: expires_at(sub_matches)?,
+                            max_downloads: sub_matches.get_one::<u64>("max-downloads").copied(),
+                        },
+                        replace: None,
+                    };
+                    if sub_matches.get_flag("watch") {
+                        let [file] = files.as_slice() else {
+                            return Err(ShcError::Usage(
+                                "--watch takes a single file or folder".to_string(),
+                            ));
+                        };
+                        if file == "-" {
+                            return Err(ShcError::Usage(
+                                "--watch cannot be used when uploading from stdin".to_string(),
+                            ));
+                        }
+                        command::add::watch(Path::new(file), &options, format, &mut api_client)
+                            .await?;
+                    } else if files.iter().any(|file| file == "-") {
+                        if files.len() > 1 {
+                            return Err(ShcError::Usage(
+                                "- cannot be combined with other files".to_string(),
+                            ));
+                        }
+                        command::add::upload_stdin(&options, format, &mut api_client).await?;
+                    } else {
+                        let file_paths = command::add::expand_paths(&files)?;
+                        if let [file_path] = file_paths.as_slice() {
+                            command::add::upload_file(file_path, &options, format, &mut api_client)
+                                .await?;
+                        } else {
+                            let jobs = *sub_matches.get_one::<u64>("jobs").expect("default");
+                            command::add::upload_files(
+                                &file_paths,
+                                &options,
+                                jobs,
+                                format,
+                                &mut api_client,
+                            )
+                            .await?;
+                        }
                     }
-                    command::add::upload_file(&file_path, &mut api_client).await?;
+                }
+
+                Some(("new", sub_matches)) => {
+                    let name = sub_matches.get_one::<String>("NAME").expect("required");
+                    command::new::new_file(name, format, &mut api_client).await?;
                 }
 
                 Some(("rename", sub_matches)) => {
                     let default: String = "".to_string();
-                    //TODO: todo rename search to filter
-                    let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::rename::rename_file(search, &mut api_client).await?;
+                    let target = sub_matches.get_one::<String>("FILE").unwrap_or(&default);
+                    let new_name = sub_matches.get_one::<String>("new-name");
+                    command::rename::rename_file(
+                        target,
+                        new_name.map(String::as_str),
+                        sub_matches.get_flag("yes"),
+                        format,
+                        &mut api_client,
+                    )
+                    .await?;
                 }
 
                 Some(("get", sub_matches)) => {
                     let default: String = "".to_string();
-                    //TODO: todo rename search to filter
-                    let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::get::download_file(search, &mut api_client).await?;
+                    let target = sub_matches.get_one::<String>("FILE").unwrap_or(&default);
+                    let options = command::get::GetOptions {
+                        out: sub_matches.get_one::<PathBuf>("out").cloned(),
+                        on_conflict: command::get::OnConflict::from_arg(
+                            sub_matches
+                                .get_one::<String>("on-conflict")
+                                .map(String::as_str)
+                                .unwrap_or_default(),
+                        ),
+                    };
+                    command::get::download_file(
+                        target,
+                        &file_filter(sub_matches),
+                        sub_matches.get_flag("yes"),
+                        &options,
+                        format,
+                        &mut api_client,
+                    )
+                    .await?;
+                }
+
+                Some(("verify", sub_matches)) => {
+                    let target = sub_matches.get_one::<String>("FILE").expect("required");
+                    let path = sub_matches.get_one::<PathBuf>("PATH").expect("required");
+                    command::verify::verify_file(target, path, format, &mut api_client).await?;
                 }
 
                 Some(("remove", sub_matches)) => {
                     let default: String = "".to_string();
-                    //TODO: todo rename search to filter
-                    let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::remove::remove_file(search, &mut api_client).await?;
+                    let target = sub_matches.get_one::<String>("FILE").unwrap_or(&default);
+                    command::remove::remove_file(
+                        target,
+                        &file_filter(sub_matches),
+                        sub_matches.get_flag("yes"),
+                        format,
+                        &mut api_client,
+                    )
+                    .await?;
                 }
 
                 Some(("visibility", sub_matches)) => {
                     let default: String = "".to_string();
-                    //TODO: todo rename search to filter
-                    let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::visibility::toggle_file_visibility(search, &mut api_client).await?;
+                    let target = sub_matches.get_one::<String>("FILE").unwrap_or(&default);
+                    command::visibility::change_file_visibility(
+                        target,
+                        &file_filter(sub_matches),
+                        visibility_flag(sub_matches),
+                        sub_matches.get_flag("yes"),
+                        format,
+                        &mut api_client,
+                    )
+                    .await?;
                 }
 
                 Some(("list", sub_matches)) => {
                     let default: String = "".to_string();
                     //TODO: todo rename search to filter
                     let search = sub_matches.get_one::<String>("FILTER").unwrap_or(&default);
-                    command::list::list_files(search, &mut api_client).await?;
+                    let options = command::list::ListOptions {
+                        page: *sub_matches.get_one::<u64>("page").expect("default"),
+                        limit: *sub_matches.get_one::<u64>("limit").expect("default"),
+                        all: sub_matches.get_flag("all"),
+                    };
+                    command::list::list_files(search, &options, format, &mut api_client).await?;
+                }
+
+                Some(("sync", sub_matches)) => {
+                    let dir = sub_matches.get_one::<PathBuf>("DIR").expect("required");
+                    let options = command::sync::SyncOptions {
+                        delete: sub_matches.get_flag("delete"),
+                        dry_run: sub_matches.get_flag("dry-run"),
+                    };
+                    command::sync::sync_dir(
+                        dir,
+                        &options,
+                        sub_matches.get_flag("yes"),
+                        format,
+                        &mut api_client,
+                    )
+                    .await?;
                 }
 
+                Some(("prune", sub_matches)) => {
+                    command::prune::prune_files(
+                        sub_matches.get_flag("dry-run"),
+                        sub_matches.get_flag("yes"),
+                        format,
+                        &mut api_client,
+                    )
+                    .await?;
+                }
+
+                None | Some(("ui", _)) => command::ui::browse(&mut api_client).await?,
+
                 _ => println!("Command not found."),
             };
         }
     };
     Ok(())
 }
+
+// the profile's settings from config.toml, with the global flags taking precedence
+fn http_settings(matches: &ArgMatches, user_config: &UserConfig) -> HttpSettings {
+    let mut http = HttpSettings::new(&user_config.user);
+    if let Some(retries) = matches.get_one::<u32>("retries") {
+        http.retries = *retries;
+    }
+    if let Some(timeout) = matches.get_one::<u64>("timeout") {
+        http.timeout = Duration::from_secs(*timeout);
+    }
+    if let Some(connect_timeout) = matches.get_one::<u64>("connect-timeout") {
+        http.connect_timeout = Duration::from_secs(*connect_timeout);
+    }
+    http
+}
+
+// the time --expires-in points at, as sent to the server
+fn expires_at(sub_matches: &ArgMatches) -> Result<Option<String>, ShcError> {
+    let Some(age) = sub_matches.get_one::<chrono::Duration>("expires-in") else {
+        return Ok(None);
+    };
+    let expires_at = chrono::Utc::now()
+        .checked_add_signed(*age)
+        .ok_or_else(|| ShcError::Usage("--expires-in is too far in the future".to_string()))?;
+    Ok(Some(
+        expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    ))
+}
+
+// Some(true) for --public, Some(false) for --private
+fn visibility_flag(sub_matches: &ArgMatches) -> Option<bool> {
+    match (
+        sub_matches.get_flag("public"),
+        sub_matches.get_flag("private"),
+    ) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+fn file_filter(sub_matches: &ArgMatches) -> command::bulk::FileFilter {
+    command::bulk::FileFilter {
+        status: sub_matches.get_one::<String>("status").cloned(),
+        older_than: sub_matches
+            .get_one::<chrono::Duration>("older-than")
+            .copied(),
+    }
+}
@@ -17,7 +17,7 @@ pub struct RefreshTokenResponse {
     pub user: User,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ShcFile {
     pub name: String,
     pub id: String,
@@ -29,6 +29,11 @@ pub struct ShcFile {
     pub user_id: String,
     pub download_url: Option<String>,
     pub upload_status: String,
+    // hex SHA-256 sent when the file was added, missing for files uploaded before that
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub download_count: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -49,3 +54,141 @@ pub struct AddFileResponse {
     pub file_name: String,
     pub is_public: bool,
 }
+
+// when a shared file should go away, sent with the file so a backend that knows them enforces them
+#[derive(Deserialize, Serialize, Clone, Default, Debug)]
+pub struct ShareLimits {
+    // RFC 3339, like updated_at
+    pub expires_at: Option<String>,
+    pub max_downloads: Option<u64>,
+}
+
+impl ShareLimits {
+    pub fn is_empty(&self) -> bool {
+        self.expires_at.is_none() && self.max_downloads.is_none()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MultipartUploadResponse {
+    pub upload_id: String,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct UploadPartResponse {
+    pub upload_url: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CompletedPart {
+    pub part_number: u64,
+    pub etag: String,
+}
+
+// results printed by the commands when a machine readable --output is used
+#[derive(Serialize, Debug)]
+pub struct AddFileResult {
+    pub file_id: String,
+    pub file_name: String,
+    pub is_public: bool,
+    pub share_link: String,
+    pub sha256: String,
+}
+
+// one row of the summary of `shc add` with several files, error is set if the upload failed
+#[derive(Serialize, Debug)]
+pub struct AddFilesResult {
+    pub path: String,
+    pub file_id: Option<String>,
+    pub file_name: Option<String>,
+    pub share_link: Option<String>,
+    pub error: Option<String>,
+}
+
+// one file of `shc sync`, action is add, update, remove or orphan, error is set if it failed
+#[derive(Serialize, Debug)]
+pub struct SyncResult {
+    pub path: String,
+    pub action: String,
+    pub file_id: Option<String>,
+    pub share_link: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RemoveFileResult {
+    pub file_id: String,
+    pub file_name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RenameFileResult {
+    pub file_id: String,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct VisibilityResult {
+    pub file_id: String,
+    pub file_name: String,
+    pub is_public: bool,
+    // false if the file already had the visibility asked for
+    pub changed: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PruneResult {
+    pub file_id: String,
+    pub file_name: String,
+    // "expired" or "download limit reached"
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct VerifyResult {
+    pub file_id: String,
+    pub file_name: String,
+    pub path: String,
+    pub sha256: String,
+    pub matches: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LoginResult {
+    pub profile: String,
+    pub email: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LogoutResult {
+    pub profile: String,
+}
+
+// one profile of `shc profile list`, also printed by `profile set`
+#[derive(Serialize, Debug)]
+pub struct ProfileResult {
+    pub name: String,
+    // the profile the command ran with
+    pub active: bool,
+    pub email: Option<String>,
+    pub api_url: String,
+    pub frontend_url: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RemoveProfileResult {
+    pub name: String,
+    // false if there was no such profile
+    pub removed: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DownloadResult {
+    pub file_id: String,
+    pub file_name: String,
+    pub path: String,
+    pub size: u64,
+    // the file already existed and --on-conflict skip was given
+    pub skipped: bool,
+}
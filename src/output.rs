@@ -0,0 +1,84 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::error::ShcError;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    // human readable output, the default
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn from_arg(format: &str) -> OutputFormat {
+        match format {
+            "json" => OutputFormat::Json,
+            "ndjson" => OutputFormat::Ndjson,
+            "csv" => OutputFormat::Csv,
+            _ => OutputFormat::Table,
+        }
+    }
+
+    pub fn is_human(&self) -> bool {
+        *self == OutputFormat::Table
+    }
+}
+
+// prints the result of a command that acts on a single file
+pub fn print_one<T: Serialize>(format: OutputFormat, item: &T) -> Result<(), ShcError> {
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(item)?;
+            ignore_broken_pipe(writeln!(std::io::stdout().lock(), "{}", json))
+        }
+        _ => print_many(format, std::slice::from_ref(item)),
+    }
+}
+
+pub fn print_many<T: Serialize>(format: OutputFormat, items: &[T]) -> Result<(), ShcError> {
+    ignore_broken_pipe(write_many(format, items))
+}
+
+// plain text rows, used for human output when stdout is piped
+pub fn print_lines(lines: &[String]) -> Result<(), ShcError> {
+    let mut stdout = std::io::stdout().lock();
+    ignore_broken_pipe(
+        lines
+            .iter()
+            .try_for_each(|line| writeln!(stdout, "{}", line)),
+    )
+}
+
+fn write_many<T: Serialize>(format: OutputFormat, items: &[T]) -> Result<(), ShcError> {
+    let mut stdout = std::io::stdout().lock();
+
+    match format {
+        OutputFormat::Table | OutputFormat::Json => {
+            writeln!(stdout, "{}", serde_json::to_string_pretty(items)?)?;
+        }
+        OutputFormat::Ndjson => {
+            for item in items {
+                writeln!(stdout, "{}", serde_json::to_string(item)?)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout);
+            for item in items {
+                writer.serialize(item)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+// `shc list --output ndjson | head` closes stdout early, that is not an error
+fn ignore_broken_pipe<E: Into<ShcError>>(res: Result<(), E>) -> Result<(), ShcError> {
+    match res.map_err(Into::into) {
+        Err(ShcError::Io(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        res => res,
+    }
+}
@@ -0,0 +1,106 @@
+// the json files in ~/.shc-cli that remember uploads, limits and synced folders
+// several shc processes can update one at the same time, so every update holds a lock on
+// `<name>.lock` while it reads, changes and writes the file, and the new content replaces the
+// old with a rename, a crash in the middle leaves the previous version instead of half a file
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::consts::SHC_CLI_FOLDER_NAME;
+
+pub fn state_path(file_name: &str) -> PathBuf {
+    dirs::home_dir()
+        .unwrap()
+        .join(SHC_CLI_FOLDER_NAME)
+        .join(file_name)
+}
+
+// a missing or broken file reads as empty, what it tracked is only lost, not wrong
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// applies `change` to the current content of the file and writes the result back
+pub fn update<T, R>(path: &Path, change: impl FnOnce(&mut T) -> R) -> io::Result<R>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let folder = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(folder)?;
+
+    // the lock is released when the file is closed, also when the process dies
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path(path))?;
+    lock_file.lock()?;
+
+    let mut contents = load(path);
+    let res = change(&mut contents);
+
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(".state")
+        .tempfile_in(folder)?;
+    temp_file.write_all(serde_json::to_string_pretty(&contents)?.as_bytes())?;
+    temp_file.as_file().sync_all()?;
+    temp_file.persist(path).map_err(|e| e.error)?;
+    Ok(res)
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+    lock_name.push(".lock");
+    path.with_file_name(lock_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let path = &path;
+                scope.spawn(move || {
+                    for i in 0..20 {
+                        update(path, |entries: &mut BTreeMap<String, u32>| {
+                            entries.insert(format!("{}-{}", thread, i), i)
+                        })
+                        .unwrap();
+                    }
+                });
+            }
+        });
+
+        let entries: BTreeMap<String, u32> = load(&path);
+        assert_eq!(entries.len(), 8 * 20);
+        // only the file and its lock are left, no temp files
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn broken_file_reads_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        fs::write(&path, "{\"half\": ").unwrap();
+
+        let entries: BTreeMap<String, u32> = load(&path);
+        assert!(entries.is_empty());
+        update(&path, |entries: &mut BTreeMap<String, u32>| {
+            entries.insert("whole".to_string(), 1)
+        })
+        .unwrap();
+        assert_eq!(load::<BTreeMap<String, u32>>(&path).len(), 1);
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::consts::SYNC_MANIFEST_FILE_NAME;
+use crate::state_file;
+
+// the uploaded copy of one local file, size and modified (in nanoseconds) tell if the file has
+// to be hashed again
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SyncedFile {
+    pub file_id: String,
+    pub size: u64,
+    pub modified: u64,
+    pub sha256: String,
+}
+
+// files uploaded by `shc sync`, per profile and folder, keyed by the path inside the folder
+pub struct SyncManifest {
+    folders: BTreeMap<String, BTreeMap<String, SyncedFile>>,
+    manifest_path: PathBuf,
+}
+
+impl SyncManifest {
+    pub fn new() -> Self {
+        let manifest_path = state_file::state_path(SYNC_MANIFEST_FILE_NAME);
+
+        // without a manifest every file counts as new
+        let folders = state_file::load(&manifest_path);
+
+        SyncManifest {
+            folders,
+            manifest_path,
+        }
+    }
+
+    // `dir` has to be canonical, the same folder always gets the same key
+    pub fn get(&self, dir: &Path, profile: &str) -> BTreeMap<String, SyncedFile> {
+        self.folders
+            .get(&folder_key(dir, profile))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set(
+        &mut self,
+        dir: &Path,
+        profile: &str,
+        files: BTreeMap<String, SyncedFile>,
+    ) -> std::io::Result<()> {
+        let key = folder_key(dir, profile);
+        // only this folder is written, another sync may have changed the others meanwhile
+        state_file::update(&self.manifest_path, |folders: &mut BTreeMap<_, _>| {
+            folders.insert(key.clone(), files.clone());
+        })?;
+        self.folders.insert(key, files);
+        Ok(())
+    }
+}
+
+fn folder_key(dir: &Path, profile: &str) -> String {
+    format!("{}:{}", profile, dir.to_string_lossy())
+}
@@ -1,82 +1,535 @@
 use chrono::DateTime;
+use console::{style, Key, Term};
 
 // what is dialoguer?
-use dialoguer::{theme, Select};
+use dialoguer::Confirm;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle, WeakProgressBar};
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::sync::Mutex;
+use std::time::Duration;
 
-use crate::consts::MAX_NAME_WIDTH_LENGTH;
+use crate::api_client::ApiClient;
+use crate::consts::{DEFAULT_PAGE_SIZE, MAX_NAME_WIDTH_LENGTH};
+use crate::error::ShcError;
 use crate::models::ShcFile;
 
 // read it now
-use crate::utils::format_bytes;
+use crate::utils::{format_bytes, parse_file_ref};
 
-pub fn shc_file_input(files: &[ShcFile], prompt: &str) -> usize {
+// fails instead of waiting for input that can never come, e.g. in CI or a pipe
+pub fn ensure_interactive(missing: &str) -> Result<(), ShcError> {
+    if std::io::stdin().is_terminal() {
+        Ok(())
+    } else {
+        Err(ShcError::Usage(format!(
+            "{} is required when stdin is not a terminal",
+            missing
+        )))
+    }
+}
+
+pub fn confirm(prompt: &str, assume_yes: bool) -> Result<(), ShcError> {
+    if assume_yes {
+        return Ok(());
+    }
+    ensure_interactive("--yes")?;
+
+    let confirm = Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?;
+
+    if confirm {
+        Ok(())
+    } else {
+        Err(ShcError::Aborted)
+    }
+}
+
+// bars on screen that redraw on their own, see hide_progress
+enum Drawn {
+    Bar(WeakProgressBar),
+    Multi(MultiProgress),
+}
+
+static DRAWN: Mutex<Vec<Drawn>> = Mutex::new(Vec::new());
+
+// the spinner shown while waiting for the API
+pub fn spinner(message: impl Into<Cow<'static, str>>) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb.set_style(
+        ProgressStyle::with_template("{spinner:.dim.bold} shc: {wide_msg}")
+            .unwrap()
+            .tick_chars("/|\\- "),
+    );
+    pb.set_message(message);
+    track_progress(&pb);
+    pb
+}
+
+// a bar drawn while API calls are made, so a prompt in between is not drawn over
+pub fn track_progress(pb: &ProgressBar) {
+    let mut drawn = DRAWN.lock().unwrap();
+    drawn.retain(|drawn| match drawn {
+        Drawn::Bar(pb) => pb.upgrade().is_some(),
+        Drawn::Multi(_) => true,
+    });
+    drawn.push(Drawn::Bar(pb.downgrade()));
+}
+
+pub fn track_multi_progress(multi: &MultiProgress) {
+    DRAWN.lock().unwrap().push(Drawn::Multi(multi.clone()));
+}
+
+// stops the tracked bars from drawing until the returned guard is dropped, their last frame
+// stays on screen and they continue below whatever was printed in between
+pub fn hide_progress() -> HiddenProgress {
+    let mut hidden = HiddenProgress::default();
+    for drawn in DRAWN.lock().unwrap().iter() {
+        match drawn {
+            Drawn::Bar(pb) => match pb.upgrade() {
+                Some(pb) if !pb.is_hidden() && !pb.is_finished() => {
+                    pb.set_draw_target(ProgressDrawTarget::hidden());
+                    hidden.bars.push(pb);
+                }
+                _ => {}
+            },
+            Drawn::Multi(multi) if !multi.is_hidden() => {
+                multi.set_draw_target(ProgressDrawTarget::hidden());
+                hidden.multis.push(multi.clone());
+            }
+            Drawn::Multi(_) => {}
+        }
+    }
+    // the cursor is still at the end of the last frame
+    if !hidden.bars.is_empty() || !hidden.multis.is_empty() {
+        eprintln!();
+    }
+    hidden
+}
+
+#[derive(Default)]
+pub struct HiddenProgress {
+    bars: Vec<ProgressBar>,
+    multis: Vec<MultiProgress>,
+}
+
+impl Drop for HiddenProgress {
+    fn drop(&mut self) {
+        for pb in &self.bars {
+            pb.set_draw_target(ProgressDrawTarget::stderr());
+        }
+        for multi in &self.multis {
+            multi.set_draw_target(ProgressDrawTarget::stderr());
+        }
+    }
+}
+
+// `target` is a file id, a share link or a filename filter for the picker
+// returns None if the filter matched no files
+pub async fn select_file(
+    target: &str,
+    prompt: &str,
+    api_client: &mut ApiClient,
+) -> Result<Option<ShcFile>, ShcError> {
+    let files = select(target, prompt, false, api_client).await?;
+    Ok(files.into_iter().next())
+}
+
+// like select_file, but the picker lets the user pick several files
+// returns an empty list if the filter matched no files
+pub async fn select_files(
+    target: &str,
+    prompt: &str,
+    api_client: &mut ApiClient,
+) -> Result<Vec<ShcFile>, ShcError> {
+    select(target, prompt, true, api_client).await
+}
+
+async fn select(
+    target: &str,
+    prompt: &str,
+    multi: bool,
+    api_client: &mut ApiClient,
+) -> Result<Vec<ShcFile>, ShcError> {
+    if let Some(file_id) = parse_file_ref(target) {
+        let pb = spinner("Fetching file...");
+        let res = api_client.get_file_download_url(&file_id).await;
+        pb.finish_and_clear();
+        return res.map(|file| vec![file]);
+    }
+
+    ensure_interactive("a file id or share link")?;
+
+    pick_files(target, 1, DEFAULT_PAGE_SIZE, prompt, multi, api_client).await
+}
+
+pub async fn pick_file(
+    search: &str,
+    page: u64,
+    limit: u64,
+    prompt: &str,
+    api_client: &mut ApiClient,
+) -> Result<Option<ShcFile>, ShcError> {
+    let files = pick_files(search, page, limit, prompt, false, api_client).await?;
+    Ok(files.into_iter().next())
+}
+
+// picker over the files matching `search`, starting at `page`
+// the next page is fetched when the cursor moves past the last loaded file
+pub async fn pick_files(
+    search: &str,
+    page: u64,
+    limit: u64,
+    prompt: &str,
+    multi: bool,
+    api_client: &mut ApiClient,
+) -> Result<Vec<ShcFile>, ShcError> {
+    let mut files: Vec<ShcFile> = vec![];
+    let mut state = PickerState::default();
+    let mut page = page;
+
+    loop {
+        let pb = spinner("Fetching files...");
+        let res = api_client.list_files(search, page, limit).await;
+        pb.finish_and_clear();
+        let res = res?;
+
+        files.extend(res.results);
+        if files.is_empty() {
+            return Ok(vec![]);
+        }
+        let next_page = res.next_page.filter(|next_page| *next_page > page);
+
+        let prompt = format!(
+            "{} ({} of {} files)",
+            prompt,
+            files.len(),
+            res.total_results
+        );
+        let selection = shc_file_input(&files, &prompt, next_page.is_some(), multi, &mut state)?;
+
+        match (selection, next_page) {
+            (Some(mut selection), _) => {
+                selection.sort_unstable();
+                return Ok(selection
+                    .into_iter()
+                    .rev()
+                    .map(|index| files.swap_remove(index))
+                    .rev()
+                    .collect());
+            }
+            // the picker comes back with the same filter, cursor and checked files
+            (None, Some(next_page)) => page = next_page,
+            (None, None) => return Ok(vec![]),
+        }
+    }
+}
+
+// what a query matched in a file, char positions to highlight in the name and mime type
+struct PickerMatch {
+    score: i64,
+    name: Vec<usize>,
+    mime_type: Vec<usize>,
+}
+
+// matches the query against the name, the mime type and the extension, in that order
+fn match_file(matcher: &SkimMatcherV2, file: &ShcFile, query: &str) -> Option<PickerMatch> {
+    if query.is_empty() {
+        return Some(PickerMatch {
+            score: 0,
+            name: vec![],
+            mime_type: vec![],
+        });
+    }
+    if let Some((score, name)) = fuzzy_match(matcher, &file.name, query) {
+        return Some(PickerMatch {
+            score,
+            name,
+            mime_type: vec![],
+        });
+    }
+    if let Some((score, mime_type)) = fuzzy_match(matcher, &file.mime_type, query) {
+        return Some(PickerMatch {
+            score,
+            name: vec![],
+            mime_type,
+        });
+    }
+    fuzzy_match(matcher, &file.extension, query).map(|(score, _)| PickerMatch {
+        score,
+        name: vec![],
+        mime_type: vec![],
+    })
+}
+
+// subsequence match like `mnrs` for `main.rs`, or for longer queries a part of `text`
+// within a typo or two of the query, like `recieve` for `receive.rs`
+// typo matches score below every subsequence match
+fn fuzzy_match(matcher: &SkimMatcherV2, text: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    if let Some(found) = matcher.fuzzy_indices(text, query) {
+        return Some(found);
+    }
+
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let typos = query.len() / 4;
+    if typos == 0 {
+        return None;
+    }
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut best: Option<(usize, usize, usize)> = None;
+    for len in query.len() - typos..=query.len() + typos {
+        for start in 0..text.len().saturating_sub(len - 1) {
+            let distance = edit_distance(&query, &text[start..start + len]);
+            if distance <= typos && best.is_none_or(|(best, _, _)| distance < best) {
+                best = Some((distance, start, len));
+            }
+        }
+    }
+    best.map(|(distance, start, len)| (-(distance as i64), (start..start + len).collect()))
+}
+
+// edits to turn `a` into `b`, swapping two neighbouring chars counts as one
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in rows[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+// `text` with the chars at `positions` highlighted, padded to `width` chars
+fn highlight(text: &str, positions: &[usize], width: usize) -> String {
+    let mut line: String = text
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match positions.contains(&i) {
+            true => style(c).cyan().bold().underlined().to_string(),
+            false => c.to_string(),
+        })
+        .collect();
+    line.push_str(&" ".repeat(width.saturating_sub(text.chars().count())));
+    line
+}
+
+// what the user did in the picker so far, kept while the next page is fetched
+#[derive(Default)]
+pub struct PickerState {
+    pub query: String,
+    // position in the list of matches
+    pub cursor: usize,
+    pub checked: Vec<usize>,
+}
+
+// fuzzy picker, typing filters the files and highlights what matched
+// with `multi` space checks files, enter returns the checked ones or the one under the cursor
+// with `has_more` moving past the last file returns None so the caller can load more files
+pub fn shc_file_input(
+    files: &[ShcFile],
+    prompt: &str,
+    has_more: bool,
+    multi: bool,
+    state: &mut PickerState,
+) -> Result<Option<Vec<usize>>, ShcError> {
     let size_width = 10;
-    let updated_at_width = 20;
-    let visibility_width = 10;
+    let updated_at_width = 14;
+    let visibility_width = 8;
 
     // what does below line do?
     let date_formatter = timeago::Formatter::new();
 
-    // what does below line do? what is the meaning of -> Result<String, Box<dyn std::error::Error>>?
-    let files =
-     
-    // what does below line do? what is the meaning of &[ShcFile]?
-        files
+    // every name is padded to the longest one so the other columns line up
+    let name_width = files
+        .iter()
+        .map(|file| file.name.chars().count().min(MAX_NAME_WIDTH_LENGTH))
+        .max()
+        .unwrap_or(0);
+    let names: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let mut name = file.name.clone();
+            if name.chars().count() > MAX_NAME_WIDTH_LENGTH {
+                name = name.chars().take(MAX_NAME_WIDTH_LENGTH - 3).collect();
+                name.push_str("...");
+            }
+            name
+        })
+        .collect();
+    let columns: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let time_ago = DateTime::parse_from_rfc3339(&file.updated_at)
+                .map(|updated_at| date_formatter.convert_chrono(updated_at, chrono::Utc::now()))
+                .unwrap_or_default();
+            let visibility = if file.is_public { "Public" } else { "Private" };
+            format!(
+                "{:<size_width$}  {:<updated_at_width$}  {:<visibility_width$}",
+                format_bytes(file.size),
+                time_ago,
+                visibility,
+            )
+        })
+        .collect();
+
+    let term = Term::stderr();
+    let matcher = SkimMatcherV2::default().ignore_case();
+    let PickerState {
+        query,
+        cursor,
+        checked,
+    } = state;
+    let mut drawn_lines = 0;
+
+    term.hide_cursor()?;
+    let selection = loop {
+        let mut matches: Vec<(usize, PickerMatch)> = files
             .iter()
-            // what does below line do? what is the meaning of |file|? what is the meaning of -> Result<String, Box<dyn std::error::Error>>?
-            .map(|file| -> Result<String, Box<dyn std::error::Error>> {
-                let mut name = file.name.clone();
-
-                println!("{} {}", name, name.len());
-                if name.len() > MAX_NAME_WIDTH_LENGTH {
-                    name.truncate(name.len() - 5);
-                    name.push_str("...");
-                }
+            .enumerate()
+            .filter_map(|(index, file)| Some((index, match_file(&matcher, file, query)?)))
+            .collect();
+        // stable, an empty query keeps the order of the server
+        matches.sort_by_key(|(_, found)| -found.score);
+        let entries = matches.len();
+        *cursor = (*cursor).min(entries.saturating_sub(1));
+
+        let (rows, width) = term.size();
+        let page_size = (rows as usize).saturating_sub(3).clamp(1, 15);
+        let first = cursor.saturating_sub(page_size - 1);
+
+        let mut lines = vec![format!(
+            "{} {} {} {}",
+            style("?").yellow().bold(),
+            style(prompt).bold(),
+            style("›").dim(),
+            query
+        )];
+        for (entry, (index, found)) in matches.iter().enumerate().skip(first).take(page_size) {
+            let pointer = match entry == *cursor {
+                true => style("❯").green().bold().to_string(),
+                false => " ".to_string(),
+            };
+            let checkbox = match (multi, checked.contains(index)) {
+                (false, _) => String::new(),
+                (true, true) => format!("{} ", style("[x]").green()),
+                (true, false) => "[ ] ".to_string(),
+            };
+            lines.push(format!(
+                "{} {}{}  {}  {}",
+                pointer,
+                checkbox,
+                highlight(&names[*index], &found.name, name_width),
+                columns[*index],
+                style(highlight(&files[*index].mime_type, &found.mime_type, 0)).dim(),
+            ));
+        }
+        if entries == 0 {
+            lines.push(style("  no matching files").dim().to_string());
+        }
+        let hint = match multi {
+            true => "type to filter, ↑↓ to move, space to check, enter to confirm",
+            false => "type to filter, ↑↓ to move, enter to confirm",
+        };
+        lines.push(style(hint).dim().to_string());
 
-                let time_ago = date_formatter.convert_chrono(
-
-                    // what does below line do?
-                    DateTime::parse_from_rfc3339(file.updated_at.as_str())?,
-                    chrono::Utc::now(),
-                );
-
-
-                // check format_bytes function in utils.rs
-                let size = format_bytes(file.size);
-                let visibility = if file.is_public {
-                    // what does to_string() do?
-                    "Public".to_string()
-                } else {
-                    "Private".to_string()
-                };
-                Ok(format!(
-                "{:<name_width$}\t\t{:<size_width$}\t{:<updated_at_width$}\t{:<visibility_width$}",
-                name, size, time_ago, visibility,
-                name_width = name.len(),
-                size_width = size_width,
-                updated_at_width = updated_at_width,
-                visibility_width = visibility_width
-            ))
-            }) 
-            // what does collect::<Result<Vec<String>, Box<dyn std::error::Error>>>() do?
-            .collect::<Result<Vec<String>, Box<dyn std::error::Error>>>();
-
-    // what does match do?
-    let files = match files {
-        Ok(items) => items,
-
-        // what does vec![] do? explain below line with examples
-        Err(_) => vec![],
+        term.clear_last_lines(drawn_lines)?;
+        for line in &lines {
+            // a wrapped line would throw off clearing it again
+            term.write_line(&console::truncate_str(line, width as usize - 1, "…"))?;
+        }
+        drawn_lines = lines.len();
+
+        let key = match term.read_key() {
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                return Err(ShcError::Interrupted)
+            }
+            key => key?,
+        };
+        let moved = match key {
+            Key::ArrowDown => Some(*cursor + 1),
+            Key::PageDown => Some(*cursor + page_size),
+            _ => None,
+        };
+        // past the last loaded file, the next page continues the list where the cursor went
+        if has_more && entries > 0 && moved.is_some_and(|moved| moved >= entries) {
+            *cursor = entries;
+            break None;
+        }
+        match key {
+            Key::ArrowUp => *cursor = cursor.saturating_sub(1),
+            Key::ArrowDown => *cursor = (*cursor + 1).min(entries.saturating_sub(1)),
+            Key::PageUp => *cursor = cursor.saturating_sub(page_size),
+            Key::PageDown => *cursor = (*cursor + page_size).min(entries.saturating_sub(1)),
+            Key::Home => *cursor = 0,
+            Key::End => *cursor = entries.saturating_sub(1),
+            Key::Char(' ') if multi => {
+                if let Some((index, _)) = matches.get(*cursor) {
+                    match checked.iter().position(|checked| checked == index) {
+                        Some(position) => {
+                            checked.remove(position);
+                        }
+                        None => checked.push(*index),
+                    }
+                }
+            }
+            Key::Enter => match matches.get(*cursor) {
+                Some(_) if !checked.is_empty() => break Some(checked.clone()),
+                Some((index, _)) => break Some(vec![*index]),
+                None => {}
+            },
+            Key::Escape if !query.is_empty() => query.clear(),
+            Key::Escape => {
+                term.clear_last_lines(drawn_lines)?;
+                term.show_cursor()?;
+                return Err(ShcError::Aborted);
+            }
+            Key::Backspace => {
+                query.pop();
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                *cursor = 0;
+            }
+            _ => {}
+        }
     };
 
-    // what does below all lines do until unwrap?
-    let selection = Select::with_theme(&theme::ColorfulTheme::default())
-        .max_length(20)
-        .with_prompt(prompt)
-        .default(0)
-        .items(&files)
-        .interact()
-        .unwrap();
+    term.clear_last_lines(drawn_lines)?;
+    term.show_cursor()?;
+    if let Some(selection) = &selection {
+        let names: Vec<&str> = selection
+            .iter()
+            .map(|&index| files[index].name.as_str())
+            .collect();
+        term.write_line(&format!(
+            "{} {} {} {}",
+            style("✔").green(),
+            style(prompt).bold(),
+            style("·").dim(),
+            style(names.join(", ")).green()
+        ))?;
+    }
 
-    selection
+    Ok(selection)
 }
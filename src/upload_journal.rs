@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::consts::UPLOAD_JOURNAL_FILE_NAME;
+use crate::models::{CompletedPart, ShareLimits};
+use crate::state_file;
+
+// progress of one chunked upload, enough to continue it after a failure
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub profile: String,
+    pub file_size: u64,
+    pub modified: u64,
+    pub file_id: String,
+    pub file_name: String,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub is_public: bool,
+    #[serde(default)]
+    pub limits: ShareLimits,
+    #[serde(default)]
+    pub sha256: String,
+    pub upload_id: String,
+    pub part_size: u64,
+    pub parts: Vec<CompletedPart>,
+}
+
+impl JournalEntry {
+    pub fn part_count(&self) -> u64 {
+        self.file_size.div_ceil(self.part_size).max(1)
+    }
+
+    pub fn uploaded_bytes(&self) -> u64 {
+        self.parts
+            .iter()
+            .map(|part| {
+                let start = (part.part_number - 1) * self.part_size;
+                self.part_size.min(self.file_size - start)
+            })
+            .sum()
+    }
+}
+
+// unfinished chunked uploads, keyed by the canonical path of the local file
+// a missing or broken journal only means nothing can be resumed
+fn load() -> BTreeMap<String, JournalEntry> {
+    state_file::load(&journal_path())
+}
+
+// returns the entry for file_path, unless the file changed since the upload started
+pub fn get(file_path: &Path, profile: &str) -> Option<JournalEntry> {
+    let (file_size, modified) = file_stamp(file_path)?;
+    load().remove(&journal_key(file_path)?).filter(|entry| {
+        entry.profile == profile && entry.file_size == file_size && entry.modified == modified
+    })
+}
+
+pub fn insert(file_path: &Path, entry: JournalEntry) -> std::io::Result<()> {
+    let Some(key) = journal_key(file_path) else {
+        return Ok(());
+    };
+    state_file::update(&journal_path(), |entries: &mut BTreeMap<_, _>| {
+        entries.insert(key, entry);
+    })
+}
+
+pub fn remove(file_path: &Path) -> std::io::Result<()> {
+    let Some(key) = journal_key(file_path) else {
+        return Ok(());
+    };
+    state_file::update(
+        &journal_path(),
+        |entries: &mut BTreeMap<String, JournalEntry>| {
+            entries.remove(&key);
+        },
+    )
+}
+
+fn journal_path() -> PathBuf {
+    state_file::state_path(UPLOAD_JOURNAL_FILE_NAME)
+}
+
+// size and modification time in nanoseconds, used to notice that a file changed between attempts
+// whole seconds would miss a file written twice within the same second
+pub fn file_stamp(file_path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(file_path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some((metadata.len(), u64::try_from(modified).ok()?))
+}
+
+fn journal_key(file_path: &Path) -> Option<String> {
+    fs::canonicalize(file_path)
+        .ok()
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn stamp_changes_within_the_same_second() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let file = fs::File::create(&path).unwrap();
+        let second = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        file.set_modified(second + Duration::from_millis(100))
+            .unwrap();
+        let first = file_stamp(&path).unwrap();
+        file.set_modified(second + Duration::from_millis(200))
+            .unwrap();
+        assert_ne!(file_stamp(&path).unwrap(), first);
+    }
+}
@@ -1,57 +1,120 @@
+use console::style;
 use serde::{Deserialize, Serialize};
 
 // what is meaning of PathBuf?
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 // when do we have to write crate?
-use crate::consts::{SHC_CLI_FOLDER_NAME, USER_CONFIG_FILE_NAME};
+use crate::consts::{
+    DEFAULT_PROFILE_NAME, SHC_BACKEND_API_BASE_URL, SHC_CLI_FOLDER_NAME, SHC_FRONTEND_BASE_URL,
+    USER_CONFIG_FILE_NAME,
+};
+use crate::credentials::{self, CredentialStore, Credentials};
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
 pub struct UserInfo {
     // what is meaning of Option<String>?
     pub email: Option<String>,
     pub name: Option<String>,
     pub user_id: Option<String>,
+    // kept in the credential store, only read from config.toml to move them there
+    #[serde(skip_serializing)]
     pub access_token: Option<String>,
+    #[serde(skip_serializing)]
     pub refresh_token: Option<String>,
+    // backend and share-link frontend of this profile, defaults are used when unset
+    pub api_base_url: Option<String>,
+    pub frontend_url: Option<String>,
+    // see http::HttpSettings, defaults are used when unset
+    pub retries: Option<u32>,
+    pub connect_timeout: Option<u64>,
+    pub timeout: Option<u64>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+impl UserInfo {
+    fn credentials(&self) -> Credentials {
+        Credentials {
+            access_token: self.access_token.clone(),
+            refresh_token: self.refresh_token.clone(),
+        }
+    }
+
+    pub fn api_base_url(&self) -> &str {
+        self.api_base_url
+            .as_deref()
+            .unwrap_or(SHC_BACKEND_API_BASE_URL)
+            .trim_end_matches('/')
+    }
+
+    pub fn frontend_url(&self) -> &str {
+        self.frontend_url
+            .as_deref()
+            .unwrap_or(SHC_FRONTEND_BASE_URL)
+            .trim_end_matches('/')
+    }
+}
+
+// layout of config.toml, one table per profile
+#[derive(Deserialize, Serialize, Default)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, UserInfo>,
+}
 
+#[derive(Clone, Debug)]
 pub struct UserConfig {
+    // name of the active profile and its settings
+    pub profile: String,
     pub user: UserInfo,
+    // every profile stored in config.toml, the active one is written back on save
+    pub profiles: BTreeMap<String, UserInfo>,
     // what is meaning of PathBuf? and what is config_path?
     pub config_path: PathBuf,
+    // tokens of every profile, see credentials::open
+    credentials: Arc<dyn CredentialStore>,
 }
 
 // what is impl?
 impl UserConfig {
     // what is meaning of Self is it similar to self in python?
-    pub fn new() -> Self {
+    pub fn new(profile: Option<&str>) -> Self {
         // what is meaning of unwrap and join?
         // what does below code do?
         let shc_folder = dirs::home_dir().unwrap().join(SHC_CLI_FOLDER_NAME);
 
-        // what is meaning of join?
-        // shc_folder is immutable so how can we call join on it?
-        let config_path = shc_folder.join(USER_CONFIG_FILE_NAME);
-
         // what is meaning of exists?
         if !shc_folder.exists() {
             // what is meaning of create_dir_all?
             std::fs::create_dir_all(&shc_folder).unwrap();
         }
+        let credentials = credentials::open(&shc_folder);
+        Self::open(&shc_folder, credentials, profile)
+    }
+
+    // the config in `shc_folder` with the tokens kept in `credentials`
+    fn open(
+        shc_folder: &Path,
+        credentials: Arc<dyn CredentialStore>,
+        profile: Option<&str>,
+    ) -> Self {
+        // what is meaning of join?
+        // shc_folder is immutable so how can we call join on it?
+        let config_path = shc_folder.join(USER_CONFIG_FILE_NAME);
+        let profile = profile.unwrap_or(DEFAULT_PROFILE_NAME).to_string();
+
         if !config_path.exists() {
             // are we intializing user_config here?
             let user_config = UserConfig {
-                user: UserInfo {
-                    email: None,
-                    name: None,
-                    user_id: None,
-                    access_token: None,
-                    refresh_token: None,
-                },
+                profile,
+                user: UserInfo::default(),
+                profiles: BTreeMap::new(),
                 config_path: config_path.clone(),
+                credentials,
             };
             user_config.save();
             return user_config;
@@ -62,29 +125,202 @@ impl UserConfig {
             fs::read_to_string(&config_path).expect("Something went wrong reading the file");
 
         // what is meaning of toml & from_str & expect?
-        let user: UserInfo = toml::from_str(&contents).expect("Could not parse TOML");
+        let table: toml::Table = toml::from_str(&contents).expect("Could not parse TOML");
+        let profiles = if table.contains_key("profiles") {
+            let config: ConfigFile = table.try_into().expect("Could not parse TOML");
+            config.profiles
+        } else {
+            // config written before profiles existed, treat it as the default profile
+            let user: UserInfo = table.try_into().expect("Could not parse TOML");
+            BTreeMap::from([(DEFAULT_PROFILE_NAME.to_string(), user)])
+        };
 
-        UserConfig {
-            user,
+        let mut user_config = UserConfig {
+            profile: profile.clone(),
+            user: profiles.get(&profile).cloned().unwrap_or_default(),
+            profiles,
             config_path: config_path.clone(),
+            credentials,
+        };
+        user_config.move_tokens_to_store();
+
+        match user_config.credentials.load(&profile) {
+            Ok(Some(credentials)) => {
+                user_config.user.access_token = credentials.access_token;
+                user_config.user.refresh_token = credentials.refresh_token;
+            }
+            // nothing stored, the tokens are still in config.toml if moving them failed
+            Ok(None) => {}
+            Err(e) => warn_credentials(&profile, e),
+        }
+        user_config
+    }
+
+    // the default profile always exists, the others once `profile set` or `login` created them
+    pub fn profile_exists(&self) -> bool {
+        self.profile == DEFAULT_PROFILE_NAME || self.profiles.contains_key(&self.profile)
+    }
+
+    // config.toml written before the credential store existed has the tokens in it
+    fn move_tokens_to_store(&self) {
+        let mut moved = false;
+        for (name, user) in &self.profiles {
+            let credentials = user.credentials();
+            if credentials.is_empty() {
+                continue;
+            }
+            // left in config.toml if the store fails, so the login is not lost
+            if let Err(e) = self.credentials.save(name, &credentials) {
+                warn_credentials(name, e);
+                return;
+            }
+            moved = true;
         }
+        if moved {
+            // tokens are not serialized, writing the profiles drops them from the file
+            self.write(self.profiles.clone());
+        }
+    }
+
+    pub fn api_base_url(&self) -> &str {
+        self.user.api_base_url()
+    }
+
+    pub fn frontend_url(&self) -> &str {
+        self.user.frontend_url()
+    }
+
+    pub fn share_link(&self, file_id: &str) -> String {
+        format!("{}/share/{}", self.frontend_url(), file_id)
     }
 
     // what is meaning of &self? and what does below code do?
     pub fn save(&self) {
-        let toml = toml::to_string(&self.user).unwrap();
+        let mut profiles = self.profiles.clone();
+        profiles.insert(self.profile.clone(), self.user.clone());
+        self.write(profiles);
+
+        let credentials = self.user.credentials();
+        let res = match credentials.is_empty() {
+            true => self.credentials.remove(&self.profile),
+            false => self.credentials.save(&self.profile, &credentials),
+        };
+        if let Err(e) = res {
+            warn_credentials(&self.profile, e);
+        }
+    }
+
+    // returns false if there was no such profile
+    pub fn remove_profile(&mut self, name: &str) -> bool {
+        let removed = self.profiles.remove(name).is_some();
+        if let Err(e) = self.credentials.remove(name) {
+            warn_credentials(name, e);
+        }
+        let mut profiles = self.profiles.clone();
+        if self.profile != name {
+            profiles.insert(self.profile.clone(), self.user.clone());
+        }
+        self.write(profiles);
+        removed
+    }
+
+    fn write(&self, profiles: BTreeMap<String, UserInfo>) {
+        let toml = toml::to_string(&ConfigFile { profiles }).unwrap();
         fs::write(&self.config_path, toml).unwrap();
     }
 
     // FIXME: not working
     pub fn clear(&mut self) {
+        // keep the settings of the profile, only forget who is logged in
         self.user = UserInfo {
-            email: None,
-            name: None,
-            user_id: None,
-            access_token: None,
-            refresh_token: None,
+            api_base_url: self.user.api_base_url.take(),
+            frontend_url: self.user.frontend_url.take(),
+            retries: self.user.retries,
+            connect_timeout: self.user.connect_timeout,
+            timeout: self.user.timeout,
+            ..UserInfo::default()
         };
         self.save();
     }
 }
+
+fn warn_credentials(profile: &str, e: std::io::Error) {
+    eprintln!(
+        "{} could not access the login of profile \"{}\": {}",
+        style("warning:").yellow().bold(),
+        profile,
+        e
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::CREDENTIALS_FILE_NAME;
+    use crate::credentials::FileStore;
+
+    // a store that can be read but not written, like a locked keyring
+    #[derive(Debug)]
+    struct ReadOnlyStore;
+
+    impl CredentialStore for ReadOnlyStore {
+        fn load(&self, _: &str) -> std::io::Result<Option<Credentials>> {
+            Ok(None)
+        }
+
+        fn save(&self, _: &str, _: &Credentials) -> std::io::Result<()> {
+            Err(std::io::Error::other("locked"))
+        }
+
+        fn remove(&self, _: &str) -> std::io::Result<()> {
+            Err(std::io::Error::other("locked"))
+        }
+    }
+
+    fn legacy_config(folder: &Path) -> PathBuf {
+        let config_path = folder.join(USER_CONFIG_FILE_NAME);
+        // written before profiles and the credential store existed
+        fs::write(
+            &config_path,
+            "email = \"me@example.com\"\naccess_token = \"access\"\nrefresh_token = \"refresh\"\n",
+        )
+        .unwrap();
+        config_path
+    }
+
+    #[test]
+    fn tokens_move_out_of_a_legacy_config() {
+        let folder = tempfile::tempdir().unwrap();
+        let config_path = legacy_config(folder.path());
+        let store = Arc::new(FileStore::new(folder.path().join(CREDENTIALS_FILE_NAME)));
+
+        let user_config = UserConfig::open(folder.path(), store.clone(), None);
+        assert_eq!(user_config.user.email.as_deref(), Some("me@example.com"));
+        assert_eq!(user_config.user.access_token.as_deref(), Some("access"));
+        assert_eq!(user_config.user.refresh_token.as_deref(), Some("refresh"));
+
+        let stored = store.load(DEFAULT_PROFILE_NAME).unwrap().unwrap();
+        assert_eq!(stored.access_token.as_deref(), Some("access"));
+        assert_eq!(stored.refresh_token.as_deref(), Some("refresh"));
+        let config = fs::read_to_string(&config_path).unwrap();
+        assert!(!config.contains("access"));
+        assert!(config.contains("me@example.com"));
+
+        // the next run finds them in the store only
+        let user_config = UserConfig::open(folder.path(), store, None);
+        assert_eq!(user_config.user.access_token.as_deref(), Some("access"));
+    }
+
+    #[test]
+    fn tokens_stay_when_the_store_cannot_take_them() {
+        let folder = tempfile::tempdir().unwrap();
+        let config_path = legacy_config(folder.path());
+
+        let user_config = UserConfig::open(folder.path(), Arc::new(ReadOnlyStore), None);
+        assert_eq!(user_config.user.access_token.as_deref(), Some("access"));
+        assert_eq!(user_config.user.refresh_token.as_deref(), Some("refresh"));
+        // left in config.toml for the next attempt
+        let config = fs::read_to_string(&config_path).unwrap();
+        assert!(config.contains("access_token"));
+    }
+}
@@ -1,12 +1,11 @@
 use ignore::WalkBuilder;
 use std::fs::{self, File};
-use std::io::{self};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use zip::write::FileOptions;
 use zip::{CompressionMethod::Bzip2, ZipWriter};
 
 use crate::consts::SHC_IGNORE_FILE_NAME;
-use tempfile::NamedTempFile;
 
 pub fn format_bytes(bytes: u64) -> String {
     let mut bytes = bytes as f64;
@@ -33,16 +32,66 @@ pub fn format_bytes(bytes: u64) -> String {
     format!("{:.2} {}", bytes, unit)
 }
 
-// leave it for now
+// text/plain if the start of the file looks like text, application/octet-stream otherwise
+pub fn sniff_mime_type(file_path: &Path) -> io::Result<&'static str> {
+    let mut head = Vec::with_capacity(8192);
+    File::open(file_path)?.take(8192).read_to_end(&mut head)?;
+
+    let is_utf8 = match std::str::from_utf8(&head) {
+        Ok(_) => true,
+        // a multi-byte character may be cut off at the end of the sample
+        Err(e) => e.error_len().is_none(),
+    };
+    let is_text = is_utf8 && !head.contains(&0);
+
+    Ok(if is_text {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    })
+}
+
+// file id from a share link or a bare id, None if `target` is neither
+pub fn parse_file_ref(target: &str) -> Option<String> {
+    let target = target.trim();
+    if let Some(index) = target.find("/share/") {
+        // whatever the link points at is the id, whatever form the server gives its ids
+        let id = target[index + "/share/".len()..]
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default();
+        return (!id.is_empty()).then(|| id.to_string());
+    }
+
+    // `<id>#key=...` of an encrypted file
+    let id = target.split('#').next().unwrap_or_default();
+    // bare words are only ids if they look like one, anything else is a filename filter
+    let is_uuid = id.len() == 36
+        && id.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+    is_uuid.then(|| id.to_string())
+}
+
+// walks what uploading the folder includes, skipping everything its .shcignore files list
+pub fn folder_walker(path: &Path) -> ignore::Walk {
+    WalkBuilder::new(path)
+        .git_ignore(false)
+        .add_custom_ignore_filename(SHC_IGNORE_FILE_NAME)
+        .build()
+}
+
+// zips the folder into a new temporary file, every call gets its own so uploads of
+// folders with the same name don't write into each other, the caller deletes it
 pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<PathBuf> {
     let src_dir = fs::canonicalize(src_dir)?;
-    let folder_name = src_dir.file_name().unwrap().to_string_lossy();
-    let dest_file_path = NamedTempFile::new_in("/tmp")?
-        .into_temp_path()
-        .with_extension("zip")
-        .with_file_name(format!("{}.zip", folder_name));
-
-    let dest_file = File::create(&dest_file_path)?;
+    let (dest_file, dest_file_path) = tempfile::Builder::new()
+        .prefix("shc-")
+        .suffix(".zip")
+        .tempfile()?
+        .keep()
+        .map_err(|e| e.error)?;
 
     let mut zip = ZipWriter::new(dest_file);
 
@@ -70,18 +119,10 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
             *current_size += file_size;
 
             if *current_size > size_limit {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Exceeded size limit for zip file",
-                ));
+                return Err(io::Error::other("Exceeded size limit for zip file"));
             }
         } else if path.is_dir() {
-            let walker = WalkBuilder::new(path)
-                .git_ignore(false)
-                .add_custom_ignore_filename(SHC_IGNORE_FILE_NAME)
-                .build();
-
-            for result in walker {
+            for result in folder_walker(path) {
                 // TODO: Handle errors
                 let entry_path = result.unwrap().into_path();
 
@@ -102,3 +143,37 @@ pub fn zip_directory_recursive(src_dir: &Path, size_limit: u64) -> io::Result<Pa
     let _total_size = zip_inner(&src_dir, &mut zip, &src_dir, size_limit, &mut current_size)?;
     Ok(dest_file_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_links_give_their_id() {
+        let id = "3f2b8c1e-9d4a-4e6f-8a7b-1c2d3e4f5a6b";
+        let link = format!("https://shc.example.com/share/{}", id);
+        assert_eq!(parse_file_ref(&link).as_deref(), Some(id));
+        assert_eq!(
+            parse_file_ref(&format!("{}#key=abc", link)).as_deref(),
+            Some(id)
+        );
+        // ids that are not uuids still come from the link
+        assert_eq!(
+            parse_file_ref("https://shc.example.com/share/abc123?ref=mail").as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(parse_file_ref("https://shc.example.com/share/"), None);
+    }
+
+    #[test]
+    fn only_uuids_are_bare_ids() {
+        let id = "3f2b8c1e-9d4a-4e6f-8a7b-1c2d3e4f5a6b";
+        assert_eq!(parse_file_ref(id).as_deref(), Some(id));
+        assert_eq!(
+            parse_file_ref(&format!("{}#key=abc", id)).as_deref(),
+            Some(id)
+        );
+        assert_eq!(parse_file_ref("report"), None);
+        assert_eq!(parse_file_ref("abc123"), None);
+    }
+}